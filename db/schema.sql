@@ -30,7 +30,25 @@ CREATE TABLE IF NOT EXISTS PROBLEMS (
     PROBLEM_TEST_CASES_URL TEXT NOT NULL,
     PROBLEM_OUTPUTS_URL TEXT NOT NULL,
     PROBLEM_MEMORY_MB_LIMIT INT NOT NULL,
-    PROBLEM_TIME_MS_LIMIT INT NOT NULL
+    PROBLEM_TIME_MS_LIMIT INT NOT NULL,
+    PROBLEM_CHECKER_MODE VARCHAR(20) NOT NULL DEFAULT 'exact' CHECK( PROBLEM_CHECKER_MODE IN ('exact', 'tokens', 'float', 'case_insensitive', 'custom') ),
+    PROBLEM_CHECKER_EPSILON DOUBLE PRECISION NOT NULL DEFAULT 0.000001 CHECK( PROBLEM_CHECKER_EPSILON >= 0 ),
+    PROBLEM_CHECKER_URL TEXT,
+    PROBLEM_INTERACTOR_URL TEXT,
+    PROBLEM_SUBTASKS_URL TEXT,
+    PROBLEM_GRADER_URL TEXT
+);
+
+-- PROBLEM TEST CASES TABLE
+-- Whether a test case is a sample, for cases set by hand. Without a row a
+-- test case is a sample when its name starts with "sample".
+CREATE TABLE IF NOT EXISTS PROBLEM_TEST_CASES (
+    PROBLEM_ID INT NOT NULL,
+    TEST_CASE_ID VARCHAR(64) NOT NULL,
+    TEST_CASE_SAMPLE BOOLEAN NOT NULL,
+
+    PRIMARY KEY (PROBLEM_ID, TEST_CASE_ID),
+    FOREIGN KEY (PROBLEM_ID) REFERENCES PROBLEMS (PROBLEM_ID) ON DELETE CASCADE
 );
 
 -- QUIZZES TABLE
@@ -63,18 +81,131 @@ CREATE TABLE IF NOT EXISTS QUIZ_ATTEMPTS (
     FOREIGN KEY (QUIZ_ID) REFERENCES QUIZZES (QUIZ_ID) ON DELETE CASCADE,
     FOREIGN KEY (USER_ID) REFERENCES USERS (USER_ID) ON DELETE CASCADE
 );
+-- LANGUAGES TABLE
+CREATE TABLE IF NOT EXISTS LANGUAGES (
+    LANGUAGE_NAME VARCHAR(50) PRIMARY KEY,
+    LANGUAGE_DISPLAY_NAME VARCHAR(100) NOT NULL,
+    LANGUAGE_VERSION VARCHAR(50) NOT NULL,
+    LANGUAGE_EXTENSION VARCHAR(10) NOT NULL,
+    LANGUAGE_TIME_MULTIPLIER DOUBLE PRECISION NOT NULL DEFAULT 1 CHECK( LANGUAGE_TIME_MULTIPLIER > 0 ),
+    LANGUAGE_MEMORY_OVERHEAD_MB INT NOT NULL DEFAULT 0 CHECK( LANGUAGE_MEMORY_OVERHEAD_MB >= 0 ),
+    LANGUAGE_ENABLED BOOLEAN NOT NULL DEFAULT TRUE
+);
+
 -- SUBMISSIONS TABLE
 CREATE TABLE IF NOT EXISTS SUBMISSIONS (
     SUBMISSION_ID SERIAL PRIMARY KEY,
     USER_ID INT NOT NULL,
     PROBLEM_ID INT NOT NULL,
-    SUBMISSION_TIME INT CHECK( SUBMISSION_TIME > 0 ),
+    SUBMISSION_LANGUAGE VARCHAR(50) NOT NULL,
+    SUBMISSION_TIME INT CHECK( SUBMISSION_TIME >= 0 ),
+    SUBMISSION_MEMORY INT CHECK( SUBMISSION_MEMORY >= 0 ),
     SUBMISSION_URL TEXT NOT NULL,
+    SUBMISSION_FILE_NAME VARCHAR(255),
+    SUBMISSION_SOURCE_HASH CHAR(64),
     SUBMISSION_EARNED_SCORE INT NOT NULL DEFAULT 0, 
-    SUBMISSION_ANSWER_CODE VARCHAR(5) NOT NULL,
+    SUBMISSION_ANSWER_CODE VARCHAR(10) NOT NULL,
+    SUBMISSION_COMPILE_OUTPUT TEXT,
+    SUBMISSION_CREATED_AT TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    SUBMISSION_JUDGED_AT TIMESTAMPTZ,
 
     FOREIGN KEY (USER_ID) REFERENCES USERS (USER_ID) ON DELETE CASCADE,
-    FOREIGN KEY (PROBLEM_ID) REFERENCES PROBLEMS (PROBLEM_ID) ON DELETE CASCADE
+    FOREIGN KEY (PROBLEM_ID) REFERENCES PROBLEMS (PROBLEM_ID) ON DELETE CASCADE,
+    FOREIGN KEY (SUBMISSION_LANGUAGE) REFERENCES LANGUAGES (LANGUAGE_NAME)
+);
+
+CREATE INDEX IF NOT EXISTS SUBMISSIONS_USER_IDX ON SUBMISSIONS (USER_ID, SUBMISSION_ID);
+CREATE INDEX IF NOT EXISTS SUBMISSIONS_PROBLEM_IDX ON SUBMISSIONS (PROBLEM_ID, SUBMISSION_ID);
+CREATE INDEX IF NOT EXISTS SUBMISSIONS_CREATED_AT_IDX ON SUBMISSIONS (SUBMISSION_CREATED_AT);
+CREATE INDEX IF NOT EXISTS SUBMISSIONS_PROBLEM_TIME_IDX ON SUBMISSIONS (PROBLEM_ID, SUBMISSION_TIME, SUBMISSION_ID);
+CREATE INDEX IF NOT EXISTS SUBMISSIONS_SOURCE_HASH_IDX ON SUBMISSIONS (USER_ID, PROBLEM_ID, SUBMISSION_SOURCE_HASH);
+
+-- SUBMISSION FILES TABLE
+-- The files of a multi-file submission after the first one, which is SUBMISSION_URL.
+CREATE TABLE IF NOT EXISTS SUBMISSION_FILES (
+    SUBMISSION_ID INT NOT NULL,
+    FILE_ORDER INT NOT NULL CHECK( FILE_ORDER > 0 ),
+    FILE_NAME VARCHAR(255) NOT NULL,
+    FILE_URL TEXT NOT NULL,
+
+    PRIMARY KEY (SUBMISSION_ID, FILE_ORDER),
+    UNIQUE (SUBMISSION_ID, FILE_NAME),
+    FOREIGN KEY (SUBMISSION_ID) REFERENCES SUBMISSIONS (SUBMISSION_ID) ON DELETE CASCADE
+);
+
+-- SUBMISSION RATE LIMITS TABLE
+-- One token bucket per user (`user:<id>`) and per user and problem (`user:<id>:problem:<id>`).
+CREATE TABLE IF NOT EXISTS SUBMISSION_RATE_LIMITS (
+    BUCKET_KEY VARCHAR(100) PRIMARY KEY,
+    BUCKET_TOKENS DOUBLE PRECISION NOT NULL,
+    BUCKET_UPDATED_AT TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- SUBMISSION RESULTS TABLE
+CREATE TABLE IF NOT EXISTS SUBMISSION_RESULTS (
+    SUBMISSION_ID INT NOT NULL,
+    RESULT_TEST_CASE VARCHAR(255) NOT NULL,
+    RESULT_ORDER INT NOT NULL,
+    RESULT_VERDICT VARCHAR(10) NOT NULL,
+    RESULT_WALL_TIME INT,
+    RESULT_MEMORY INT,
+    RESULT_SIGNAL VARCHAR(20),
+    RESULT_EXIT_CODE INT,
+    RESULT_STDERR TEXT,
+
+    PRIMARY KEY (SUBMISSION_ID, RESULT_TEST_CASE),
+    FOREIGN KEY (SUBMISSION_ID) REFERENCES SUBMISSIONS (SUBMISSION_ID) ON DELETE CASCADE
+);
+
+-- SUBMISSION SUBTASK SCORES TABLE
+CREATE TABLE IF NOT EXISTS SUBMISSION_SUBTASK_SCORES (
+    SUBMISSION_ID INT NOT NULL,
+    SUBTASK_ID INT NOT NULL,
+    SUBTASK_ORDER INT NOT NULL,
+    SUBTASK_VERDICT VARCHAR(10) NOT NULL,
+    SUBTASK_SCORE INT NOT NULL,
+    SUBTASK_EARNED_SCORE INT NOT NULL,
+
+    PRIMARY KEY (SUBMISSION_ID, SUBTASK_ID),
+    FOREIGN KEY (SUBMISSION_ID) REFERENCES SUBMISSIONS (SUBMISSION_ID) ON DELETE CASCADE
+);
+
+-- JUDGE JOBS TABLE
+CREATE TABLE IF NOT EXISTS JUDGE_JOBS (
+    JOB_ID SERIAL PRIMARY KEY,
+    SUBMISSION_ID INT NOT NULL,
+    JOB_LANGUAGE VARCHAR(50) NOT NULL,
+    JOB_LANGUAGE_VERSION VARCHAR(50) NOT NULL,
+    JOB_STATUS VARCHAR(10) NOT NULL DEFAULT 'QUEUED' CHECK( JOB_STATUS IN ('QUEUED', 'RUNNING', 'DONE', 'FAILED') ),
+    JOB_ATTEMPTS INT NOT NULL DEFAULT 0,
+    JOB_LAST_ERROR TEXT,
+    JOB_CREATED_AT TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    JOB_LOCKED_AT TIMESTAMPTZ,
+    JOB_HEARTBEAT_AT TIMESTAMPTZ,
+    JOB_FINISHED_AT TIMESTAMPTZ,
+    JOB_REQUESTED_BY INT,
+    JOB_RETRY_AT TIMESTAMPTZ,
+
+    FOREIGN KEY (SUBMISSION_ID) REFERENCES SUBMISSIONS (SUBMISSION_ID) ON DELETE CASCADE,
+    FOREIGN KEY (JOB_REQUESTED_BY) REFERENCES USERS (USER_ID) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS JUDGE_JOBS_STATUS_IDX ON JUDGE_JOBS (JOB_STATUS, JOB_ID);
+CREATE INDEX IF NOT EXISTS JUDGE_JOBS_SUBMISSION_IDX ON JUDGE_JOBS (SUBMISSION_ID);
+
+-- SUBMISSION VERDICT HISTORY TABLE
+CREATE TABLE IF NOT EXISTS SUBMISSION_VERDICT_HISTORY (
+    HISTORY_ID SERIAL PRIMARY KEY,
+    SUBMISSION_ID INT NOT NULL,
+    JOB_ID INT,
+    HISTORY_PREVIOUS_VERDICT VARCHAR(10) NOT NULL,
+    HISTORY_PREVIOUS_SCORE INT NOT NULL,
+    HISTORY_VERDICT VARCHAR(10) NOT NULL,
+    HISTORY_SCORE INT NOT NULL,
+    HISTORY_CREATED_AT TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    FOREIGN KEY (SUBMISSION_ID) REFERENCES SUBMISSIONS (SUBMISSION_ID) ON DELETE CASCADE,
+    FOREIGN KEY (JOB_ID) REFERENCES JUDGE_JOBS (JOB_ID) ON DELETE SET NULL
 );
 
 -- CONTEST REGISTRIES TABLE
@@ -99,6 +230,47 @@ CREATE TABLE IF NOT EXISTS PROBLEM_SETS (
     FOREIGN KEY (CONTEST_ID) REFERENCES CONTESTS(CONTEST_ID) ON DELETE CASCADE
 );
 
+-- PLAGIARISM REPORTS TABLE
+CREATE TABLE IF NOT EXISTS PLAGIARISM_REPORTS (
+    REPORT_ID SERIAL PRIMARY KEY,
+    PROBLEM_ID INT,
+    CONTEST_ID INT,
+    REPORT_THRESHOLD DOUBLE PRECISION NOT NULL CHECK( REPORT_THRESHOLD >= 0 AND REPORT_THRESHOLD <= 1 ),
+    REPORT_SUBMISSIONS INT NOT NULL,
+    REPORT_REQUESTED_BY INT,
+    REPORT_CREATED_AT TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    FOREIGN KEY (PROBLEM_ID) REFERENCES PROBLEMS (PROBLEM_ID) ON DELETE CASCADE,
+    FOREIGN KEY (CONTEST_ID) REFERENCES CONTESTS (CONTEST_ID) ON DELETE CASCADE,
+    FOREIGN KEY (REPORT_REQUESTED_BY) REFERENCES USERS (USER_ID) ON DELETE SET NULL
+);
+
+-- PLAGIARISM PAIRS TABLE
+CREATE TABLE IF NOT EXISTS PLAGIARISM_PAIRS (
+    PAIR_ID SERIAL PRIMARY KEY,
+    REPORT_ID INT NOT NULL,
+    SUBMISSION_A INT NOT NULL,
+    SUBMISSION_B INT NOT NULL,
+    PAIR_SIMILARITY DOUBLE PRECISION NOT NULL,
+
+    FOREIGN KEY (REPORT_ID) REFERENCES PLAGIARISM_REPORTS (REPORT_ID) ON DELETE CASCADE,
+    FOREIGN KEY (SUBMISSION_A) REFERENCES SUBMISSIONS (SUBMISSION_ID) ON DELETE CASCADE,
+    FOREIGN KEY (SUBMISSION_B) REFERENCES SUBMISSIONS (SUBMISSION_ID) ON DELETE CASCADE
+);
+
+-- PLAGIARISM REGIONS TABLE
+CREATE TABLE IF NOT EXISTS PLAGIARISM_REGIONS (
+    PAIR_ID INT NOT NULL,
+    REGION_ORDER INT NOT NULL,
+    REGION_A_START_LINE INT NOT NULL,
+    REGION_A_END_LINE INT NOT NULL,
+    REGION_B_START_LINE INT NOT NULL,
+    REGION_B_END_LINE INT NOT NULL,
+
+    PRIMARY KEY (PAIR_ID, REGION_ORDER),
+    FOREIGN KEY (PAIR_ID) REFERENCES PLAGIARISM_PAIRS (PAIR_ID) ON DELETE CASCADE
+);
+
 -- MATERIALS TABLE
 CREATE TABLE IF NOT EXISTS MATERIALS (
     MATERIAL_ID SERIAL PRIMARY KEY,
@@ -119,5 +291,12 @@ INSERT INTO USER_ROLES (USER_ROLE_NAME) VALUES
 ('STUDENT'),
 ('PROFESSOR');
 
+INSERT INTO LANGUAGES (LANGUAGE_NAME, LANGUAGE_DISPLAY_NAME, LANGUAGE_VERSION, LANGUAGE_EXTENSION, LANGUAGE_TIME_MULTIPLIER, LANGUAGE_MEMORY_OVERHEAD_MB) VALUES
+('cpp', 'C++', '10.2.0', 'cpp', 1, 0),
+('c', 'C', '10.2.0', 'c', 1, 0),
+('python', 'Python 3', '3.10.0', 'py', 2, 0),
+('java', 'Java', '15.0.2', 'java', 2, 64)
+ON CONFLICT (LANGUAGE_NAME) DO NOTHING;
+
 INSERT INTO USERS (USERNAME, USER_PASSWORD, USER_EMAIL,USER_ROLE ) VALUES
 ( 'dou-admin', '$2b$12$FCcjcrl5gRThTh9TQ0LZxOz9ryk/4.oAhTIl.CoIQeBopksm89Yba', 'dou-admin@doucode.duckdns.org' ,2 );
\ No newline at end of file
@@ -16,7 +16,7 @@ use fs_extra::dir::copy as copy_dir;
 use fs_extra::file::copy as copy_file;
 
 use crate::models::models::{Problem};
-use crate::utils::validations::{validate_limits, validate_test_cases_structure};
+use crate::utils::validations::{validate_checker, validate_grader, validate_limits, validate_subtasks, validate_test_cases_structure};
 
 pub async fn create_problem(
     Extension(pool): Extension<PgPool>,
@@ -25,6 +25,8 @@ pub async fn create_problem(
     let mut name = String::new();
     let mut t_limit = 0;
     let mut m_limit = 0;
+    let mut checker_mode = String::from("exact");
+    let mut checker_epsilon = 1e-6;
     let mut zip_data = None;
 
     while let Some(field) = multipart.next_field().await.unwrap_or(None) {
@@ -38,6 +40,11 @@ pub async fn create_problem(
                 let text = field.text().await.unwrap_or_default();
                 m_limit = text.parse().unwrap_or(0);
             }
+            Some("checker_mode") => checker_mode = field.text().await.unwrap_or_default(),
+            Some("checker_epsilon") => {
+                let text = field.text().await.unwrap_or_default();
+                checker_epsilon = text.parse().unwrap_or(-1.0);
+            }
             Some("zip") => {
                 let bytes = field.bytes().await.unwrap_or_default();
                 zip_data = Some(bytes);
@@ -60,6 +67,13 @@ pub async fn create_problem(
         ));
     }
 
+    if !validate_checker(&checker_mode, checker_epsilon) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Invalid checker mode or epsilon"})),
+        ));
+    }
+
     let zip_bytes = zip_data.ok_or_else(|| {
         (
             StatusCode::BAD_REQUEST,
@@ -101,6 +115,33 @@ pub async fn create_problem(
         ));
     }
 
+    let checker_src = statement_src.join("checker.cpp");
+    if checker_mode == "custom" && !checker_src.exists() {
+        let _ = fs::remove_dir_all(&temp_path).await;
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "Custom checker mode requires statement/checker.cpp in the zip"})),
+        ));
+    }
+
+    let has_subtasks = statement_src.join("subtasks.json").exists();
+    if has_subtasks && let Err(e) = validate_subtasks(&statement_src) {
+        let _ = fs::remove_dir_all(&temp_path).await;
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": e})),
+        ));
+    }
+
+    let has_grader = statement_src.join("grader").is_dir();
+    if has_grader && let Err(e) = validate_grader(&statement_src) {
+        let _ = fs::remove_dir_all(&temp_path).await;
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": e})),
+        ));
+    }
+
     let problem_id = Uuid::new_v4();
     let problem_path = format!("/app/problems/{}", problem_id);
     let statement_dst = Path::new(&problem_path).join("statement");
@@ -114,14 +155,14 @@ pub async fn create_problem(
 
     let options = fs_extra::dir::CopyOptions::new();
 
-    copy_dir(&statement_src.join("testCases"), &statement_dst, &options).map_err(|e| {
+    copy_dir(statement_src.join("testCases"), &statement_dst, &options).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"error": format!("Failed to copy testCases: {}", e)})),
         )
     })?;
 
-    copy_dir(&statement_src.join("outputs"), &statement_dst, &options).map_err(|e| {
+    copy_dir(statement_src.join("outputs"), &statement_dst, &options).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"error": format!("Failed to copy outputs: {}", e)})),
@@ -129,8 +170,8 @@ pub async fn create_problem(
     })?;
 
     copy_file(
-        &statement_src.join("statement.txt"),
-        &statement_dst.join("statement.txt"),
+        statement_src.join("statement.txt"),
+        statement_dst.join("statement.txt"),
         &fs_extra::file::CopyOptions::new(),
     ).map_err(|e| {
         (
@@ -139,6 +180,48 @@ pub async fn create_problem(
         )
     })?;
 
+    if has_grader {
+        copy_dir(statement_src.join("grader"), &statement_dst, &options).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to copy grader: {}", e)})),
+            )
+        })?;
+    }
+
+    let has_interactor = statement_src.join("interactor.cpp").exists();
+    let mut package_files = vec![];
+    if checker_mode == "custom" {
+        package_files.push("checker.cpp");
+    }
+    if has_interactor {
+        package_files.push("interactor.cpp");
+    }
+    if !package_files.is_empty() {
+        package_files.push("testlib.h");
+    }
+    if has_subtasks {
+        package_files.push("subtasks.json");
+    }
+
+    for file in package_files {
+        let src = statement_src.join(file);
+        if !src.exists() {
+            continue;
+        }
+        copy_file(&src, statement_dst.join(file), &fs_extra::file::CopyOptions::new()).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to copy {}: {}", file, e)})),
+            )
+        })?;
+    }
+
+    let checker_url = (checker_mode == "custom").then(|| format!("{}/statement/checker.cpp", problem_path));
+    let interactor_url = has_interactor.then(|| format!("{}/statement/interactor.cpp", problem_path));
+    let subtasks_url = has_subtasks.then(|| format!("{}/statement/subtasks.json", problem_path));
+    let grader_url = has_grader.then(|| format!("{}/statement/grader", problem_path));
+
     let _ = fs::remove_dir_all(&temp_path).await;
 
     let statement_url = format!("{}/statement/statement.txt", problem_path);
@@ -152,9 +235,15 @@ pub async fn create_problem(
             PROBLEM_TEST_CASES_URL,
             PROBLEM_OUTPUTS_URL,
             PROBLEM_MEMORY_MB_LIMIT,
-            PROBLEM_TIME_MS_LIMIT
+            PROBLEM_TIME_MS_LIMIT,
+            PROBLEM_CHECKER_MODE,
+            PROBLEM_CHECKER_EPSILON,
+            PROBLEM_CHECKER_URL,
+            PROBLEM_INTERACTOR_URL,
+            PROBLEM_SUBTASKS_URL,
+            PROBLEM_GRADER_URL
         )
-        VALUES ($1, $2, $3, $4, $5, $6)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
         RETURNING
             PROBLEM_ID,
             PROBLEM_NAME,
@@ -162,7 +251,13 @@ pub async fn create_problem(
             PROBLEM_TEST_CASES_URL,
             PROBLEM_OUTPUTS_URL,
             PROBLEM_MEMORY_MB_LIMIT,
-            PROBLEM_TIME_MS_LIMIT
+            PROBLEM_TIME_MS_LIMIT,
+            PROBLEM_CHECKER_MODE,
+            PROBLEM_CHECKER_EPSILON,
+            PROBLEM_CHECKER_URL,
+            PROBLEM_INTERACTOR_URL,
+            PROBLEM_SUBTASKS_URL,
+            PROBLEM_GRADER_URL
     ";
 
     let result = sqlx::query(query)
@@ -170,8 +265,14 @@ pub async fn create_problem(
         .bind(&statement_url)
         .bind(&test_cases_url)
         .bind(&outputs_url)
-        .bind(t_limit)
         .bind(m_limit)
+        .bind(t_limit)
+        .bind(&checker_mode)
+        .bind(checker_epsilon)
+        .bind(&checker_url)
+        .bind(&interactor_url)
+        .bind(&subtasks_url)
+        .bind(&grader_url)
         .fetch_one(&pool)
         .await;
 
@@ -185,6 +286,12 @@ pub async fn create_problem(
                 problem_outputs_url: row.get("problem_outputs_url"),
                 problem_memory_mb_limit: row.get("problem_memory_mb_limit"),
                 problem_time_ms_limit: row.get("problem_time_ms_limit"),
+                problem_checker_mode: row.get("problem_checker_mode"),
+                problem_checker_epsilon: row.get("problem_checker_epsilon"),
+                problem_checker_url: row.get("problem_checker_url"),
+                problem_interactor_url: row.get("problem_interactor_url"),
+                problem_subtasks_url: row.get("problem_subtasks_url"),
+                problem_grader_url: row.get("problem_grader_url"),
             };
             Ok((StatusCode::CREATED, Json(response)))
         }
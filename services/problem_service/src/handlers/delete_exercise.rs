@@ -36,17 +36,17 @@ pub async fn delete_problem(
 
     let statement_url: String = row.get("problem_statement_url");
     let path_parts: Vec<&str> = statement_url.split('/').collect();
-    let uuid_folder = path_parts.get(3).map(|s| *s).unwrap_or_default();
+    let uuid_folder = path_parts.get(3).copied().unwrap_or_default();
 
     let full_path = format!("/app/problems/{}", uuid_folder);
 
-    if StdPath::new(&full_path).exists() {
-        if let Err(e) = fs::remove_dir_all(&full_path).await {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": format!("Failed to delete problem folder: {}", e) })),
-            );
-        }
+    if StdPath::new(&full_path).exists()
+        && let Err(e) = fs::remove_dir_all(&full_path).await
+    {
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("Failed to delete problem folder: {}", e) })),
+        );
     }
 
     match sqlx::query("DELETE FROM problems WHERE problem_id = $1")
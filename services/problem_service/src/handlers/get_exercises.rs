@@ -17,6 +17,12 @@ pub struct ProblemWithStatement {
     problem_outputs_url: String,
     problem_memory_mb_limit: i32,
     problem_time_ms_limit: i32,
+    problem_checker_mode: String,
+    problem_checker_epsilon: f64,
+    problem_checker_url: Option<String>,
+    problem_interactor_url: Option<String>,
+    problem_subtasks_url: Option<String>,
+    problem_grader_url: Option<String>,
 }
 
 pub async fn get_problems(
@@ -30,7 +36,13 @@ pub async fn get_problems(
             problem_test_cases_url,
             problem_outputs_url,
             problem_memory_mb_limit,
-            problem_time_ms_limit
+            problem_time_ms_limit,
+            problem_checker_mode,
+            problem_checker_epsilon,
+            problem_checker_url,
+            problem_interactor_url,
+            problem_subtasks_url,
+            problem_grader_url
         FROM problems
         ORDER BY problem_id ASC
     ";
@@ -57,6 +69,12 @@ pub async fn get_problems(
                     problem_outputs_url: row.get("problem_outputs_url"),
                     problem_memory_mb_limit: row.get("problem_memory_mb_limit"),
                     problem_time_ms_limit: row.get("problem_time_ms_limit"),
+                    problem_checker_mode: row.get("problem_checker_mode"),
+                    problem_checker_epsilon: row.get("problem_checker_epsilon"),
+                    problem_checker_url: row.get("problem_checker_url"),
+                    problem_interactor_url: row.get("problem_interactor_url"),
+                    problem_subtasks_url: row.get("problem_subtasks_url"),
+                    problem_grader_url: row.get("problem_grader_url"),
                 });
             }
 
@@ -78,7 +96,13 @@ pub async fn get_problems_by_id(
             problem_test_cases_url,
             problem_outputs_url,
             problem_memory_mb_limit,
-            problem_time_ms_limit
+            problem_time_ms_limit,
+            problem_checker_mode,
+            problem_checker_epsilon,
+            problem_checker_url,
+            problem_interactor_url,
+            problem_subtasks_url,
+            problem_grader_url
         FROM problems
         WHERE problem_id = $1;
     ";
@@ -103,6 +127,12 @@ pub async fn get_problems_by_id(
                 problem_outputs_url: row.get("problem_outputs_url"),
                 problem_memory_mb_limit: row.get("problem_memory_mb_limit"),
                 problem_time_ms_limit: row.get("problem_time_ms_limit"),
+                problem_checker_mode: row.get("problem_checker_mode"),
+                problem_checker_epsilon: row.get("problem_checker_epsilon"),
+                problem_checker_url: row.get("problem_checker_url"),
+                problem_interactor_url: row.get("problem_interactor_url"),
+                problem_subtasks_url: row.get("problem_subtasks_url"),
+                problem_grader_url: row.get("problem_grader_url"),
             };
             Ok((StatusCode::OK, Json(problem)))
         }
@@ -1,3 +1,5 @@
 pub mod create_exercise;
 pub mod get_exercises;
-pub mod delete_exercise;
\ No newline at end of file
+pub mod delete_exercise;
+pub mod update_exercise;
+pub mod test_cases;
\ No newline at end of file
@@ -0,0 +1,446 @@
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Extension, Path, Query},
+    http::{header, StatusCode},
+    Json,
+    response::{IntoResponse, Response},
+};
+use sqlx::{PgConnection, PgPool, Row};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use uuid::Uuid;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path as StdPath, PathBuf};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::models::models::{TestCaseInfo, TestCaseMark};
+use crate::utils::auth::{is_professor, require_professor, AuthenticatedUser};
+use crate::utils::errors::{bad_request, database_error, internal_error, ApiError};
+use crate::utils::validations::is_valid_test_case_id;
+
+#[derive(Deserialize)]
+pub struct TestCaseQuery {
+    #[serde(default)]
+    file: Option<String>,
+}
+
+struct ProblemFolders {
+    inputs: PathBuf,
+    outputs: PathBuf,
+    subtasks: Option<PathBuf>,
+}
+
+/// Every test case for professors; students only see the samples.
+pub async fn list_test_cases(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(problem_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let folders = load_problem(&mut conn, problem_id, false).await?;
+    let samples = load_sample_marks(&mut conn, problem_id).await?;
+
+    let mut test_cases = Vec::new();
+    for (id, input_path) in read_inputs(&folders.inputs).await? {
+        let sample = is_sample(&samples, &id);
+        if !sample && !is_professor(&claims) {
+            continue;
+        }
+
+        let input = read_file(&input_path).await?;
+        let output = read_file(&folders.outputs.join(format!("{}.out", id))).await?;
+
+        test_cases.push(TestCaseInfo {
+            sample,
+            input_size: input.len() as u64,
+            output_size: output.len() as u64,
+            input_sha256: format!("{:x}", Sha256::digest(&input)),
+            output_sha256: format!("{:x}", Sha256::digest(&output)),
+            id,
+        });
+    }
+
+    // The order the judge runs them in.
+    test_cases.sort_by(|a, b| b.sample.cmp(&a.sample).then_with(|| natural_cmp(&a.id, &b.id)));
+
+    Ok((StatusCode::OK, Json(test_cases)))
+}
+
+/// The input and output of a test case, or only one of them as a file with
+/// `file=input` or `file=output`. Students can only get samples.
+pub async fn get_test_case(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path((problem_id, test_id)): Path<(i32, String)>,
+    Extension(pool): Extension<PgPool>,
+    Query(query): Query<TestCaseQuery>,
+) -> Result<Response, ApiError> {
+    let mut conn = pool.acquire().await.map_err(database_error)?;
+    let folders = load_problem(&mut conn, problem_id, false).await?;
+    let input_path = find_input(&folders.inputs, &test_id).await?;
+    let samples = load_sample_marks(&mut conn, problem_id).await?;
+    let sample = is_sample(&samples, &test_id);
+
+    if !sample {
+        require_professor(&claims)?;
+    }
+    let output_path = folders.outputs.join(format!("{}.out", test_id));
+
+    let download = |path: &StdPath, content: Vec<u8>| {
+        let file_name = path.file_name().and_then(|f| f.to_str()).unwrap_or(&test_id).to_string();
+        let headers = [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)),
+        ];
+        (StatusCode::OK, headers, content).into_response()
+    };
+
+    match query.file.as_deref() {
+        Some("input") => Ok(download(&input_path, read_file(&input_path).await?)),
+        Some("output") => Ok(download(&output_path, read_file(&output_path).await?)),
+        Some(_) => Err(bad_request("file must be input or output".to_string())),
+        None => {
+            let input = read_file(&input_path).await?;
+            let output = read_file(&output_path).await?;
+
+            let body = json!({
+                "id": test_id,
+                "sample": sample,
+                "input": String::from_utf8_lossy(&input),
+                "output": String::from_utf8_lossy(&output),
+            });
+
+            Ok((StatusCode::OK, Json(body)).into_response())
+        }
+    }
+}
+
+/// Adds `input` and `output` as test case `id`, by default the next free
+/// number. Problems with subtasks need the `subtask` it goes into.
+pub async fn add_test_case(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(problem_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    require_professor(&claims)?;
+
+    let mut test_id = None;
+    let mut input: Option<Bytes> = None;
+    let mut output: Option<Bytes> = None;
+    let mut sample = None;
+    let mut subtask = None;
+
+    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+        match field.name() {
+            Some("id") => test_id = Some(field.text().await.unwrap_or_default()),
+            Some("input") => input = Some(field.bytes().await.unwrap_or_default()),
+            Some("output") => output = Some(field.bytes().await.unwrap_or_default()),
+            Some("sample") => {
+                let text = field.text().await.unwrap_or_default();
+                sample = Some(text.trim().parse::<bool>().map_err(|_| bad_request("sample must be true or false".to_string()))?);
+            }
+            Some("subtask") => {
+                let text = field.text().await.unwrap_or_default();
+                subtask = Some(text.trim().parse::<i64>().map_err(|_| bad_request("Invalid subtask".to_string()))?);
+            }
+            _ => {}
+        }
+    }
+
+    let input = input.ok_or_else(|| bad_request("Missing required file: input".to_string()))?;
+    let output = output.ok_or_else(|| bad_request("Missing required file: output".to_string()))?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    let folders = load_problem(&mut tx, problem_id, true).await?;
+    let existing = read_inputs(&folders.inputs).await?;
+
+    let test_id = match test_id {
+        Some(test_id) => test_id.trim().to_string(),
+        None => {
+            let last = existing.keys().filter_map(|id| id.parse::<u64>().ok()).max().unwrap_or(0);
+            (last + 1).to_string()
+        }
+    };
+
+    if !is_valid_test_case_id(&test_id) {
+        return Err(bad_request(format!("Invalid test case id {}", test_id)));
+    }
+    if existing.contains_key(&test_id) {
+        return Err((StatusCode::CONFLICT, Json(json!({ "error": format!("Test case {} already exists", test_id) }))));
+    }
+
+    let subtasks = match (&folders.subtasks, subtask) {
+        (Some(subtasks_path), Some(subtask)) => {
+            let mut subtasks = read_subtasks(subtasks_path).await?;
+            let tests = subtasks
+                .iter_mut()
+                .find(|entry| entry["id"].as_i64() == Some(subtask))
+                .and_then(|entry| entry["tests"].as_array_mut())
+                .ok_or_else(|| bad_request(format!("Subtask {} not found", subtask)))?;
+            tests.push(json!(test_id));
+            Some((subtasks_path, subtasks))
+        }
+        (Some(_), None) => return Err(bad_request("The problem has subtasks, so subtask is required".to_string())),
+        (None, Some(_)) => return Err(bad_request("The problem has no subtasks".to_string())),
+        (None, None) => None,
+    };
+
+    if let Some(sample) = sample {
+        save_sample_mark(&mut tx, problem_id, &test_id, sample).await?;
+    }
+
+    // The judge only reads test cases whose input exists, so the output goes
+    // first and the subtasks that reference the case last.
+    write_atomically(&folders.outputs.join(format!("{}.out", test_id)), &output).await?;
+    write_atomically(&folders.inputs.join(format!("{}.in", test_id)), &input).await?;
+    if let Some((subtasks_path, subtasks)) = subtasks {
+        write_subtasks(subtasks_path, &subtasks).await?;
+    }
+
+    tx.commit().await.map_err(database_error)?;
+
+    let samples = HashMap::from_iter(sample.map(|sample| (test_id.clone(), sample)));
+    let body = TestCaseInfo {
+        sample: is_sample(&samples, &test_id),
+        input_size: input.len() as u64,
+        output_size: output.len() as u64,
+        input_sha256: format!("{:x}", Sha256::digest(&input)),
+        output_sha256: format!("{:x}", Sha256::digest(&output)),
+        id: test_id,
+    };
+
+    Ok((StatusCode::CREATED, Json(body)))
+}
+
+/// Marks a test case as sample (visible) or hidden.
+pub async fn mark_test_case(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path((problem_id, test_id)): Path<(i32, String)>,
+    Extension(pool): Extension<PgPool>,
+    Json(mark): Json<TestCaseMark>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_professor(&claims)?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    let folders = load_problem(&mut tx, problem_id, true).await?;
+    find_input(&folders.inputs, &test_id).await?;
+
+    save_sample_mark(&mut tx, problem_id, &test_id, mark.sample).await?;
+    tx.commit().await.map_err(database_error)?;
+
+    Ok((StatusCode::OK, Json(json!({ "id": test_id, "sample": mark.sample }))))
+}
+
+/// Deletes a test case and takes it out of the subtasks, which can't be left
+/// without test cases.
+pub async fn delete_test_case(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path((problem_id, test_id)): Path<(i32, String)>,
+    Extension(pool): Extension<PgPool>,
+) -> Result<impl IntoResponse, ApiError> {
+    require_professor(&claims)?;
+
+    let mut tx = pool.begin().await.map_err(database_error)?;
+    let folders = load_problem(&mut tx, problem_id, true).await?;
+    let input_path = find_input(&folders.inputs, &test_id).await?;
+
+    let remaining = read_inputs(&folders.inputs).await?.len() - 1;
+    if remaining == 0 {
+        return Err(bad_request("A problem needs at least one test case".to_string()));
+    }
+
+    if let Some(subtasks_path) = &folders.subtasks {
+        let mut subtasks = read_subtasks(subtasks_path).await?;
+        for entry in subtasks.iter_mut() {
+            let Some(tests) = entry["tests"].as_array_mut() else {
+                continue;
+            };
+            tests.retain(|test| test.as_str() != Some(test_id.as_str()));
+            if tests.is_empty() {
+                return Err(bad_request(format!("Subtask {} would have no test cases", entry["id"])));
+            }
+        }
+        write_subtasks(subtasks_path, &subtasks).await?;
+    }
+
+    sqlx::query("DELETE FROM PROBLEM_TEST_CASES WHERE PROBLEM_ID = $1 AND TEST_CASE_ID = $2")
+        .bind(problem_id)
+        .bind(&test_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    // The reverse order of add_test_case: once the input is gone the judge
+    // no longer looks for the output.
+    fs::remove_file(&input_path)
+        .await
+        .map_err(|e| internal_error(format!("Failed to delete {}: {}", input_path.display(), e)))?;
+    let _ = fs::remove_file(folders.outputs.join(format!("{}.out", test_id))).await;
+
+    tx.commit().await.map_err(database_error)?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": format!("Test case {} deleted", test_id) }))))
+}
+
+// With `lock` the problem row stays locked until the transaction ends, so
+// edits of its test cases and problem updates don't interleave.
+async fn load_problem(conn: &mut PgConnection, problem_id: i32, lock: bool) -> Result<ProblemFolders, ApiError> {
+    let query = format!(
+        "SELECT PROBLEM_TEST_CASES_URL, PROBLEM_OUTPUTS_URL, PROBLEM_SUBTASKS_URL FROM PROBLEMS WHERE PROBLEM_ID = $1{}",
+        if lock { " FOR UPDATE" } else { "" }
+    );
+
+    let row = sqlx::query(&query)
+        .bind(problem_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({ "error": "Problem not found" }))))?;
+
+    Ok(ProblemFolders {
+        inputs: PathBuf::from(row.get::<String, _>("problem_test_cases_url")),
+        outputs: PathBuf::from(row.get::<String, _>("problem_outputs_url")),
+        subtasks: row.get::<Option<String>, _>("problem_subtasks_url").map(PathBuf::from),
+    })
+}
+
+async fn load_sample_marks(conn: &mut PgConnection, problem_id: i32) -> Result<HashMap<String, bool>, ApiError> {
+    let rows = sqlx::query("SELECT TEST_CASE_ID, TEST_CASE_SAMPLE FROM PROBLEM_TEST_CASES WHERE PROBLEM_ID = $1")
+        .bind(problem_id)
+        .fetch_all(conn)
+        .await
+        .map_err(database_error)?;
+
+    Ok(rows.iter().map(|row| (row.get("test_case_id"), row.get("test_case_sample"))).collect())
+}
+
+async fn save_sample_mark(conn: &mut PgConnection, problem_id: i32, test_id: &str, sample: bool) -> Result<(), ApiError> {
+    sqlx::query("
+        INSERT INTO PROBLEM_TEST_CASES (PROBLEM_ID, TEST_CASE_ID, TEST_CASE_SAMPLE)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (PROBLEM_ID, TEST_CASE_ID) DO UPDATE SET TEST_CASE_SAMPLE = EXCLUDED.TEST_CASE_SAMPLE
+    ")
+        .bind(problem_id)
+        .bind(test_id)
+        .bind(sample)
+        .execute(conn)
+        .await
+        .map_err(database_error)?;
+
+    Ok(())
+}
+
+// Same rule as the judge: a mark set by hand, otherwise the `sample` prefix.
+fn is_sample(samples: &HashMap<String, bool>, id: &str) -> bool {
+    samples
+        .get(id)
+        .copied()
+        .unwrap_or_else(|| id.to_ascii_lowercase().starts_with("sample"))
+}
+
+/// Compares names chunk by chunk, with runs of digits compared as numbers, so
+/// `2` comes before `10`. A copy of the judge's ordering (`natural_cmp` in
+/// submission_service's judge/testdata.rs); the two must stay the same.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chunks = chunks(a).into_iter();
+    let mut b_chunks = chunks(b).into_iter();
+
+    loop {
+        let ordering = match (a_chunks.next(), b_chunks.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u128>(), y.parse::<u128>()) {
+                (Ok(x_number), Ok(y_number)) => x_number.cmp(&y_number),
+                _ => x.cmp(y),
+            },
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn chunks(name: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    for (index, c) in name.char_indices().skip(1) {
+        let previous = name[..index].chars().next_back().unwrap_or(c);
+        if previous.is_ascii_digit() != c.is_ascii_digit() {
+            chunks.push(&name[start..index]);
+            start = index;
+        }
+    }
+
+    if start < name.len() {
+        chunks.push(&name[start..]);
+    }
+
+    chunks
+}
+
+/// Input files by test case id, which is the file name without extension.
+async fn read_inputs(dir: &StdPath) -> Result<HashMap<String, PathBuf>, ApiError> {
+    let mut inputs = HashMap::new();
+    let mut entries = fs::read_dir(dir)
+        .await
+        .map_err(|e| internal_error(format!("Failed to read testCases: {}", e)))?;
+
+    while let Some(entry) = entries.next_entry().await.map_err(|e| internal_error(format!("Failed to read testCases: {}", e)))? {
+        let path = entry.path();
+        if path.is_file()
+            && let Some(id) = path.file_stem().and_then(|f| f.to_str())
+        {
+            inputs.insert(id.to_string(), path.clone());
+        }
+    }
+
+    Ok(inputs)
+}
+
+async fn find_input(dir: &StdPath, test_id: &str) -> Result<PathBuf, ApiError> {
+    read_inputs(dir)
+        .await?
+        .remove(test_id)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({ "error": format!("Test case {} not found", test_id) }))))
+}
+
+async fn read_file(path: &StdPath) -> Result<Vec<u8>, ApiError> {
+    fs::read(path)
+        .await
+        .map_err(|e| internal_error(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+async fn read_subtasks(path: &StdPath) -> Result<Vec<Value>, ApiError> {
+    let content = read_file(path).await?;
+    serde_json::from_slice(&content).map_err(|e| internal_error(format!("Invalid subtasks.json: {}", e)))
+}
+
+async fn write_subtasks(path: &StdPath, subtasks: &[Value]) -> Result<(), ApiError> {
+    let content = serde_json::to_vec_pretty(subtasks).map_err(|e| internal_error(e.to_string()))?;
+    write_atomically(path, &content).await
+}
+
+// Written outside the test case folders and renamed into place, so the judge
+// never reads a half-written file.
+async fn write_atomically(path: &StdPath, content: &[u8]) -> Result<(), ApiError> {
+    let statement_dir = path
+        .parent()
+        .and_then(|folder| folder.parent())
+        .ok_or_else(|| internal_error(format!("Unexpected path {}", path.display())))?;
+    let temp_path = statement_dir.join(format!(".{}.tmp", Uuid::new_v4()));
+
+    let written = match fs::write(&temp_path, content).await {
+        Ok(()) => fs::rename(&temp_path, path).await,
+        Err(e) => Err(e),
+    };
+
+    written.map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        internal_error(format!("Failed to write {}: {}", path.display(), e))
+    })
+}
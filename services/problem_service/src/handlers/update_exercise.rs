@@ -0,0 +1,365 @@
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Extension, Path},
+    http::StatusCode,
+    Json,
+    response::IntoResponse,
+};
+use sqlx::{PgPool, Row};
+use tokio::fs;
+use uuid::Uuid;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path as StdPath, PathBuf};
+use zip::ZipArchive;
+use std::io::Cursor;
+use serde_json::json;
+
+use fs_extra::dir::copy as copy_dir;
+use fs_extra::file::copy as copy_file;
+
+use crate::models::models::Problem;
+use crate::utils::auth::{require_professor, AuthenticatedUser};
+use crate::utils::errors::{bad_request, database_error, internal_error, ApiError};
+use crate::utils::validations::{validate_limits, validate_subtasks, validate_test_cases_structure};
+
+#[derive(Default)]
+struct ProblemUpdate {
+    name: Option<String>,
+    t_limit: Option<i32>,
+    m_limit: Option<i32>,
+    statement: Option<Bytes>,
+    zip: Option<Bytes>,
+    append_tests: bool,
+}
+
+/// `PUT /{problem_id}`: name and limits are required, the statement and the
+/// test data are only replaced when sent.
+pub async fn replace_problem(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(problem_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    require_professor(&claims)?;
+
+    let update = read_update(multipart).await?;
+
+    for (field, missing) in [("name", update.name.is_none()), ("t_limit", update.t_limit.is_none()), ("m_limit", update.m_limit.is_none())] {
+        if missing {
+            return Err(bad_request(format!("Missing required field: {}", field)));
+        }
+    }
+
+    apply_update(&pool, problem_id, update).await
+}
+
+/// `PATCH /{problem_id}`: only the fields sent change.
+pub async fn patch_problem(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Path(problem_id): Path<i32>,
+    Extension(pool): Extension<PgPool>,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    require_professor(&claims)?;
+
+    let update = read_update(multipart).await?;
+    apply_update(&pool, problem_id, update).await
+}
+
+async fn read_update(mut multipart: Multipart) -> Result<ProblemUpdate, ApiError> {
+    let mut update = ProblemUpdate::default();
+
+    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+        match field.name() {
+            Some("name") => update.name = Some(field.text().await.unwrap_or_default()),
+            Some("t_limit") => {
+                let text = field.text().await.unwrap_or_default();
+                update.t_limit = Some(text.trim().parse().map_err(|_| bad_request("Invalid t_limit".to_string()))?);
+            }
+            Some("m_limit") => {
+                let text = field.text().await.unwrap_or_default();
+                update.m_limit = Some(text.trim().parse().map_err(|_| bad_request("Invalid m_limit".to_string()))?);
+            }
+            Some("statement") => update.statement = Some(field.bytes().await.unwrap_or_default()),
+            Some("zip") => update.zip = Some(field.bytes().await.unwrap_or_default()),
+            Some("tests_mode") => {
+                update.append_tests = match field.text().await.unwrap_or_default().as_str() {
+                    "replace" => false,
+                    "append" => true,
+                    _ => return Err(bad_request("tests_mode must be replace or append".to_string())),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    if update.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(bad_request("The name can't be empty".to_string()));
+    }
+
+    Ok(update)
+}
+
+async fn apply_update(pool: &PgPool, problem_id: i32, update: ProblemUpdate) -> Result<impl IntoResponse + use<>, ApiError> {
+    let mut tx = pool.begin().await.map_err(database_error)?;
+
+    // The row lock keeps two updates of the same problem from building their
+    // new folder out of the same old one.
+    let row = sqlx::query("
+        SELECT
+            PROBLEM_NAME,
+            PROBLEM_STATEMENT_URL,
+            PROBLEM_MEMORY_MB_LIMIT,
+            PROBLEM_TIME_MS_LIMIT,
+            PROBLEM_SUBTASKS_URL
+        FROM PROBLEMS
+        WHERE PROBLEM_ID = $1
+        FOR UPDATE
+    ")
+        .bind(problem_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(json!({ "error": "Problem not found" }))))?;
+
+    let name = update.name.clone().unwrap_or_else(|| row.get("problem_name"));
+    let m_limit = update.m_limit.unwrap_or_else(|| row.get("problem_memory_mb_limit"));
+    let t_limit = update.t_limit.unwrap_or_else(|| row.get("problem_time_ms_limit"));
+    let mut subtasks_url: Option<String> = row.get("problem_subtasks_url");
+
+    if !validate_limits(m_limit, t_limit) {
+        return Err(bad_request("Invalid memory or time limit".to_string()));
+    }
+
+    // /app/problems/{uuid}/statement/statement.txt
+    let statement_url: String = row.get("problem_statement_url");
+    let problem_path = StdPath::new(&statement_url)
+        .parent()
+        .and_then(|statement_dir| statement_dir.parent())
+        .map(|path| path.to_path_buf())
+        .ok_or_else(|| internal_error(format!("Unexpected statement path {}", statement_url)))?;
+
+    let staging_path = if update.statement.is_some() || update.zip.is_some() {
+        let staging_path = sibling_path(&problem_path, "staging");
+        if let Err(e) = build_staging(&problem_path, &staging_path, &update).await {
+            let _ = fs::remove_dir_all(&staging_path).await;
+            return Err(e);
+        }
+
+        subtasks_url = staging_path
+            .join("statement/subtasks.json")
+            .exists()
+            .then(|| format!("{}/statement/subtasks.json", problem_path.display()));
+
+        Some(staging_path)
+    } else {
+        None
+    };
+
+    let result = sqlx::query("
+        UPDATE PROBLEMS
+        SET
+            PROBLEM_NAME = $2,
+            PROBLEM_MEMORY_MB_LIMIT = $3,
+            PROBLEM_TIME_MS_LIMIT = $4,
+            PROBLEM_SUBTASKS_URL = $5
+        WHERE PROBLEM_ID = $1
+        RETURNING
+            PROBLEM_ID,
+            PROBLEM_NAME,
+            PROBLEM_STATEMENT_URL,
+            PROBLEM_TEST_CASES_URL,
+            PROBLEM_OUTPUTS_URL,
+            PROBLEM_MEMORY_MB_LIMIT,
+            PROBLEM_TIME_MS_LIMIT,
+            PROBLEM_CHECKER_MODE,
+            PROBLEM_CHECKER_EPSILON,
+            PROBLEM_CHECKER_URL,
+            PROBLEM_INTERACTOR_URL,
+            PROBLEM_SUBTASKS_URL,
+            PROBLEM_GRADER_URL
+    ")
+        .bind(problem_id)
+        .bind(name.trim())
+        .bind(m_limit)
+        .bind(t_limit)
+        .bind(&subtasks_url)
+        .fetch_one(&mut *tx)
+        .await;
+
+    // Replaced test cases are new ones, even when they reuse a name.
+    let result = match result {
+        Ok(row) if update.zip.is_some() && !update.append_tests => sqlx::query("DELETE FROM PROBLEM_TEST_CASES WHERE PROBLEM_ID = $1")
+            .bind(problem_id)
+            .execute(&mut *tx)
+            .await
+            .map(|_| row),
+        result => result,
+    };
+
+    let row = match result {
+        Ok(row) => row,
+        Err(e) => {
+            if let Some(staging_path) = &staging_path {
+                let _ = fs::remove_dir_all(staging_path).await;
+            }
+            return Err(database_error(e));
+        }
+    };
+
+    // After the exchange the staging path holds the old folder, which is put
+    // back if the row can't be committed and dropped once it is.
+    if let Some(staging_path) = &staging_path
+        && let Err(e) = exchange_dirs(&problem_path, staging_path)
+    {
+        let _ = fs::remove_dir_all(staging_path).await;
+        return Err(internal_error(format!("Failed to swap the problem folder: {}", e)));
+    }
+
+    if let Err(e) = tx.commit().await {
+        if let Some(staging_path) = &staging_path {
+            let _ = exchange_dirs(&problem_path, staging_path);
+            let _ = fs::remove_dir_all(staging_path).await;
+        }
+        return Err(database_error(e));
+    }
+
+    if let Some(staging_path) = &staging_path {
+        let _ = fs::remove_dir_all(staging_path).await;
+    }
+
+    let response = Problem {
+        problem_id: row.get("problem_id"),
+        problem_name: row.get("problem_name"),
+        problem_statement_url: row.get("problem_statement_url"),
+        problem_test_cases_url: row.get("problem_test_cases_url"),
+        problem_outputs_url: row.get("problem_outputs_url"),
+        problem_memory_mb_limit: row.get("problem_memory_mb_limit"),
+        problem_time_ms_limit: row.get("problem_time_ms_limit"),
+        problem_checker_mode: row.get("problem_checker_mode"),
+        problem_checker_epsilon: row.get("problem_checker_epsilon"),
+        problem_checker_url: row.get("problem_checker_url"),
+        problem_interactor_url: row.get("problem_interactor_url"),
+        problem_subtasks_url: row.get("problem_subtasks_url"),
+        problem_grader_url: row.get("problem_grader_url"),
+    };
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Copies the problem folder next to it and applies the new statement and
+/// test cases to the copy, which must still be a valid problem.
+async fn build_staging(problem_path: &StdPath, staging_path: &StdPath, update: &ProblemUpdate) -> Result<(), ApiError> {
+    fs::create_dir_all(staging_path)
+        .await
+        .map_err(|e| internal_error(format!("Failed to create staging folder: {}", e)))?;
+
+    let mut options = fs_extra::dir::CopyOptions::new();
+    options.content_only = true;
+    copy_dir(problem_path, staging_path, &options)
+        .map_err(|e| internal_error(format!("Failed to copy the problem folder: {}", e)))?;
+
+    let statement_dst = staging_path.join("statement");
+
+    if let Some(statement) = &update.statement {
+        fs::write(statement_dst.join("statement.txt"), statement)
+            .await
+            .map_err(|e| internal_error(format!("Failed to write statement.txt: {}", e)))?;
+    }
+
+    if let Some(zip_bytes) = &update.zip {
+        let temp_path = format!("/tmp/{}", Uuid::new_v4());
+        let result = apply_test_zip(zip_bytes, StdPath::new(&temp_path), &statement_dst, update.append_tests).await;
+        let _ = fs::remove_dir_all(&temp_path).await;
+        result?;
+    }
+
+    if !validate_test_cases_structure(&statement_dst) {
+        return Err(bad_request("Invalid folder structure. Every test case in testCases/ needs its output in outputs/.".to_string()));
+    }
+
+    if statement_dst.join("subtasks.json").exists() {
+        validate_subtasks(&statement_dst).map_err(bad_request)?;
+    }
+
+    Ok(())
+}
+
+/// The zip has the layout of `create_problem`: `statement/testCases/`,
+/// `statement/outputs/` and optionally `statement/subtasks.json`.
+async fn apply_test_zip(zip_bytes: &Bytes, temp_path: &StdPath, statement_dst: &StdPath, append: bool) -> Result<(), ApiError> {
+    let mut archive = ZipArchive::new(Cursor::new(zip_bytes))
+        .map_err(|e| bad_request(format!("Invalid zip file: {}", e)))?;
+
+    archive
+        .extract(temp_path)
+        .map_err(|e| internal_error(format!("Failed to extract zip: {}", e)))?;
+
+    let statement_src = temp_path.join("statement");
+    if !statement_src.join("testCases").is_dir() || !statement_src.join("outputs").is_dir() {
+        return Err(bad_request("The zip must include statement/testCases/ and statement/outputs/".to_string()));
+    }
+
+    for folder in ["testCases", "outputs"] {
+        let dst = statement_dst.join(folder);
+
+        if !append {
+            let _ = fs::remove_dir_all(&dst).await;
+            fs::create_dir_all(&dst)
+                .await
+                .map_err(|e| internal_error(format!("Failed to create {}: {}", folder, e)))?;
+        }
+
+        let entries = std::fs::read_dir(statement_src.join(folder))
+            .map_err(|e| internal_error(format!("Failed to read {}: {}", folder, e)))?;
+
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let target = dst.join(&file_name);
+            if target.exists() {
+                return Err(bad_request(format!("{}/{} already exists", folder, file_name.to_string_lossy())));
+            }
+
+            copy_file(entry.path(), &target, &fs_extra::file::CopyOptions::new())
+                .map_err(|e| internal_error(format!("Failed to copy {}: {}", file_name.to_string_lossy(), e)))?;
+        }
+    }
+
+    let subtasks_src = statement_src.join("subtasks.json");
+    if subtasks_src.exists() {
+        let mut options = fs_extra::file::CopyOptions::new();
+        options.overwrite = true;
+        copy_file(&subtasks_src, statement_dst.join("subtasks.json"), &options)
+            .map_err(|e| internal_error(format!("Failed to copy subtasks.json: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+// RENAME_EXCHANGE swaps both folders in a single step, so the judge always
+// finds a complete problem under `problem_path`, the old one or the new one.
+fn exchange_dirs(problem_path: &StdPath, staging_path: &StdPath) -> std::io::Result<()> {
+    let problem_path = CString::new(problem_path.as_os_str().as_bytes())?;
+    let staging_path = CString::new(staging_path.as_os_str().as_bytes())?;
+
+    let result = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            problem_path.as_ptr(),
+            libc::AT_FDCWD,
+            staging_path.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        )
+    };
+
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+fn sibling_path(problem_path: &StdPath, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}.{}-{}", problem_path.display(), suffix, Uuid::new_v4()))
+}
@@ -1,12 +1,5 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize)]
-pub struct CreateProblem {
-    pub problem_name: String,
-    pub time_limit: i32,
-    pub memory_limit: i32,
-}
-
 #[derive(Serialize)]
 pub struct Problem {
     pub problem_id: i32,
@@ -16,6 +9,43 @@ pub struct Problem {
     pub problem_outputs_url: String,
     pub problem_memory_mb_limit: i32,
     pub problem_time_ms_limit: i32,
+    pub problem_checker_mode: String,
+    pub problem_checker_epsilon: f64,
+    pub problem_checker_url: Option<String>,
+    pub problem_interactor_url: Option<String>,
+    pub problem_subtasks_url: Option<String>,
+    pub problem_grader_url: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct Subtask {
+    pub id: i32,
+    pub score: i32,
+    pub tests: Vec<String>,
+    #[serde(default)]
+    pub depends_on: Vec<i32>,
+}
 
+#[derive(Serialize)]
+pub struct TestCaseInfo {
+    pub id: String,
+    pub sample: bool,
+    pub input_size: u64,
+    pub output_size: u64,
+    pub input_sha256: String,
+    pub output_sha256: String,
+}
+
+#[derive(Deserialize)]
+pub struct TestCaseMark {
+    pub sample: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub name: String,
+    pub email: String,
+    pub role: String,
+    pub exp: usize,
+}
@@ -1,13 +1,17 @@
 use axum::{
-    routing::{get, post, put, delete},
+    routing::{get, delete},
     Router,
 };
 use crate::handlers::{
-    create_exercise::create_problem, delete_exercise::delete_problem, get_exercises::{get_problems, get_problems_by_id}   
+    create_exercise::create_problem, delete_exercise::delete_problem, get_exercises::{get_problems, get_problems_by_id},
+    update_exercise::{patch_problem, replace_problem},
+    test_cases::{add_test_case, delete_test_case, get_test_case, list_test_cases, mark_test_case}
 };
 
 pub fn create_router() -> Router {
     Router::new()
         .route("/", get(get_problems) .post(create_problem))
-        .route("/{problem_id}", delete(delete_problem) .get(get_problems_by_id))
+        .route("/{problem_id}", delete(delete_problem) .get(get_problems_by_id) .put(replace_problem) .patch(patch_problem))
+        .route("/{problem_id}/tests", get(list_test_cases) .post(add_test_case))
+        .route("/{problem_id}/tests/{test_id}", get(get_test_case) .patch(mark_test_case) .delete(delete_test_case))
 }
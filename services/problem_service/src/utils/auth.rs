@@ -0,0 +1,55 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+use serde_json::json;
+use std::env;
+use crate::models::models::Claims;
+use crate::utils::errors::ApiError;
+
+pub struct AuthenticatedUser(pub Claims);
+
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth_header = parts
+            .headers
+            .get("authorization")
+            .and_then(|h| h.to_str().ok());
+
+        if let Some(token) = auth_header.and_then(|h| h.strip_prefix("Bearer ")) {
+            let secret = env::var("JWT_SECRET").expect("JWT_SECRET not set");
+
+            let decoded = decode::<Claims>(
+                token,
+                &DecodingKey::from_secret(secret.as_ref()),
+                &Validation::new(Algorithm::HS256),
+            )
+            .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+            return Ok(AuthenticatedUser(decoded.claims));
+        }
+
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+pub fn is_professor(claims: &Claims) -> bool {
+    claims.role == "PROFESSOR"
+}
+
+pub fn require_professor(claims: &Claims) -> Result<(), ApiError> {
+    if !is_professor(claims) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Only professors can do this" })),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use axum::{http::StatusCode, Json};
+use serde_json::{json, Value};
+
+pub type ApiError = (StatusCode, Json<Value>);
+
+pub fn bad_request(error: String) -> ApiError {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": error })))
+}
+
+pub fn internal_error(error: String) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": error })))
+}
+
+pub fn database_error(e: sqlx::Error) -> ApiError {
+    internal_error(format!("Database error: {}", e))
+}
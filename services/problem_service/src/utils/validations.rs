@@ -1,10 +1,22 @@
-use std::fs;
+use std::collections::HashSet;
 use std::path::Path;
+use crate::models::models::Subtask;
+
+pub const CHECKER_MODES: [&str; 5] = ["exact", "tokens", "float", "case_insensitive", "custom"];
 
 pub fn validate_limits(memory: i32, time: i32) -> bool {
     memory > 0 && time > 0
 }
 
+pub fn validate_checker(mode: &str, epsilon: f64) -> bool {
+    CHECKER_MODES.contains(&mode) && epsilon.is_finite() && epsilon >= 0.0
+}
+
+/// Test case ids become file names, so they are kept to letters, digits, `_` and `-`.
+pub fn is_valid_test_case_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
 pub fn validate_test_cases_structure(problem_dir: &Path) -> bool {
     let statement_path = problem_dir.join("statement.txt");
     let inputs_path = problem_dir.join("testCases");
@@ -29,3 +41,81 @@ pub fn validate_test_cases_structure(problem_dir: &Path) -> bool {
 
     true
 }
+
+/// Every test case has to belong to some subtask, and a subtask can only
+/// depend on the ones listed before it.
+pub fn validate_subtasks(problem_dir: &Path) -> Result<(), String> {
+    let content = std::fs::read_to_string(problem_dir.join("subtasks.json"))
+        .map_err(|e| format!("Cannot read subtasks.json: {}", e))?;
+    let subtasks: Vec<Subtask> = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid subtasks.json: {}", e))?;
+
+    if subtasks.is_empty() {
+        return Err("subtasks.json must define at least one subtask".to_string());
+    }
+
+    let test_cases: HashSet<String> = std::fs::read_dir(problem_dir.join("testCases"))
+        .map_err(|e| format!("Cannot read testCases: {}", e))?
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .map(|file_name| file_name.split('.').next().unwrap_or("").to_string())
+        .collect();
+
+    let mut seen_ids = HashSet::new();
+    let mut covered = HashSet::new();
+
+    for subtask in &subtasks {
+        if subtask.score < 0 {
+            return Err(format!("Subtask {} has a negative score", subtask.id));
+        }
+        if subtask.tests.is_empty() {
+            return Err(format!("Subtask {} has no test cases", subtask.id));
+        }
+        if let Some(dependency) = subtask.depends_on.iter().find(|id| !seen_ids.contains(*id)) {
+            return Err(format!("Subtask {} depends on {}, which is not defined before it", subtask.id, dependency));
+        }
+        if let Some(test) = subtask.tests.iter().find(|test| !test_cases.contains(*test)) {
+            return Err(format!("Subtask {} references unknown test case {}", subtask.id, test));
+        }
+        if !seen_ids.insert(subtask.id) {
+            return Err(format!("Duplicated subtask id {}", subtask.id));
+        }
+        covered.extend(subtask.tests.iter().cloned());
+    }
+
+    if let Some(test) = test_cases.iter().find(|test| !covered.contains(*test)) {
+        return Err(format!("Test case {} does not belong to any subtask", test));
+    }
+
+    Ok(())
+}
+
+/// `grader/` holds one folder per language (named like the submission
+/// language, e.g. `grader/cpp/`) with the files compiled with the submission.
+pub fn validate_grader(problem_dir: &Path) -> Result<(), String> {
+    let entries = std::fs::read_dir(problem_dir.join("grader"))
+        .map_err(|e| format!("Cannot read grader: {}", e))?;
+
+    let mut languages = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().into_string().unwrap_or_default();
+        if !entry.path().is_dir() {
+            return Err(format!("grader/{} must be a folder named after a language", name));
+        }
+
+        let has_files = std::fs::read_dir(entry.path())
+            .map_err(|e| format!("Cannot read grader/{}: {}", name, e))?
+            .flatten()
+            .any(|file| file.path().is_file());
+        if !has_files {
+            return Err(format!("grader/{} has no files", name));
+        }
+        languages += 1;
+    }
+
+    if languages == 0 {
+        return Err("grader must have a folder for at least one language".to_string());
+    }
+
+    Ok(())
+}
@@ -3,9 +3,11 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Row};
 use tokio::fs::read_to_string;
 use serde::{Deserialize, Serialize};
+use crate::utils::auth::{can_view, AuthenticatedUser};
 
 #[derive(Deserialize)]
 pub struct AttemptRequest {
@@ -20,19 +22,34 @@ pub struct Submission {
     problem_id: i32,
     submission_content: String,
     submission_answer_code: String,
+    submission_language: String,
+    submission_time: Option<i32>,
+    submission_memory: Option<i32>,
+    submission_created_at: DateTime<Utc>,
+    submission_judged_at: Option<DateTime<Utc>>,
 }
 
 pub async fn get_attemps(
+    AuthenticatedUser(claims): AuthenticatedUser,
     Extension(pool): Extension<PgPool>,
     Json(payload): Json<AttemptRequest>,
 ) -> Result<(StatusCode, Json<Vec<Submission>>), StatusCode> {
+    if !can_view(&claims, payload.user_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     let query = "
         SELECT
             submission_id,
             user_id,
             problem_id,
             submission_url,
-            submission_answer_code
+            submission_answer_code,
+            submission_language,
+            submission_time,
+            submission_memory,
+            submission_created_at,
+            submission_judged_at
         FROM submissions
         WHERE user_id = $1 AND problem_id = $2
         ORDER BY submission_id ASC
@@ -61,6 +78,11 @@ pub async fn get_attemps(
                     problem_id: row.get("problem_id"),
                     submission_content: submisssion_content_file,
                     submission_answer_code: row.get("submission_answer_code"),
+                    submission_language: row.get("submission_language"),
+                    submission_time: row.get("submission_time"),
+                    submission_memory: row.get("submission_memory"),
+                    submission_created_at: row.get("submission_created_at"),
+                    submission_judged_at: row.get("submission_judged_at"),
                 });
             }
 
@@ -1,76 +1,198 @@
 use axum::{
-    extract::{Extension, Path},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     Json,
 };
-use sqlx::{PgPool, Row};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+use sqlx::postgres::PgRow;
 use tokio::fs::read_to_string;
 use serde::Serialize;
+use crate::models::result_models::{SubtaskResult, TestCaseResult};
+use crate::models::submission_models::{SubmissionQuery, SubmissionSort};
+use crate::utils::auth::{can_view, visible_owner, AuthenticatedUser};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
 
 #[derive(Serialize)]
 pub struct Submission {
 	submission_id: i32,
     user_id: i32,
     problem_id: i32,
-    submission_content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    submission_content: Option<String>,
     submission_answer_code: String,
+    submission_earned_score: i32,
+    submission_language: String,
+    submission_time: Option<i32>,
+    submission_memory: Option<i32>,
+    submission_created_at: DateTime<Utc>,
+    submission_judged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct SubmissionPage {
+    submissions: Vec<Submission>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SubmissionDetail {
+    #[serde(flatten)]
+    submission: Submission,
+    // Names of the submitted files, entry point first.
+    files: Vec<String>,
+    compile_output: Option<String>,
+    results: Vec<TestCaseResult>,
+    subtasks: Vec<SubtaskResult>,
 }
 
+/// Lists submissions page by page, by id or by fastest / lightest run.
+/// Professors can see every submission, students only their own.
 pub async fn get_submissions(
+    AuthenticatedUser(claims): AuthenticatedUser,
     Extension(pool): Extension<PgPool>,
-) -> Result<(StatusCode, Json<Vec<Submission>>), StatusCode> {
-    let query = "
-        SELECT
-            submission_id,
-            user_id,
-            problem_id,
-            submission_url,
-            submission_answer_code
-        FROM submissions
-        ORDER BY submission_id ASC
-    ";
+    Query(params): Query<SubmissionQuery>,
+) -> Result<(StatusCode, Json<SubmissionPage>), StatusCode> {
+    let user_id = match (visible_owner(&claims)?, params.user_id) {
+        (Some(owner), Some(user_id)) if owner != user_id => return Err(StatusCode::FORBIDDEN),
+        (owner, user_id) => owner.or(user_id),
+    };
 
-    let rows = sqlx::query(query).fetch_all(&pool).await;
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
 
-    match rows {
-        Ok(rows) => {
-            let mut submissions = Vec::new();
+    let mut query = QueryBuilder::<Postgres>::new("
+        SELECT
+            S.SUBMISSION_ID,
+            S.USER_ID,
+            S.PROBLEM_ID,
+            S.SUBMISSION_URL,
+            S.SUBMISSION_ANSWER_CODE,
+            S.SUBMISSION_EARNED_SCORE,
+            S.SUBMISSION_LANGUAGE,
+            S.SUBMISSION_TIME,
+            S.SUBMISSION_MEMORY,
+            S.SUBMISSION_CREATED_AT,
+            S.SUBMISSION_JUDGED_AT
+        FROM SUBMISSIONS S
+        WHERE TRUE
+    ");
 
-            for row in rows {
-                let submission_path: String = row.get("submission_url");
+    if let Some(user_id) = user_id {
+        query.push(" AND S.USER_ID = ").push_bind(user_id);
+    }
+    if let Some(problem_id) = params.problem_id {
+        query.push(" AND S.PROBLEM_ID = ").push_bind(problem_id);
+    }
+    if let Some(contest_id) = params.contest_id {
+        query
+            .push(" AND S.PROBLEM_ID IN (SELECT PROBLEM_ID FROM PROBLEM_SETS WHERE CONTEST_ID = ")
+            .push_bind(contest_id)
+            .push(")");
+    }
+    if let Some(veredict) = &params.veredict {
+        query.push(" AND S.SUBMISSION_ANSWER_CODE = ").push_bind(veredict);
+    }
+    if let Some(lang) = &params.lang {
+        query.push(" AND S.SUBMISSION_LANGUAGE = ").push_bind(lang);
+    }
+    if let Some(from) = params.from {
+        query.push(" AND S.SUBMISSION_CREATED_AT >= ").push_bind(from);
+    }
+    if let Some(to) = params.to {
+        query.push(" AND S.SUBMISSION_CREATED_AT < ").push_bind(to);
+    }
 
-                let submisssion_content_file = match read_to_string(&submission_path).await {
-                    Ok(content) => content,
-                    Err(_) => String::from("[Error al leer el envío]"),
-                };
+    let key_column = sort_key_column(params.sort);
+    if let Some(column) = key_column {
+        // Submissions that never ran have nothing to rank by.
+        query.push(format!(" AND {} IS NOT NULL", column));
+    }
 
-                submissions.push(Submission {
-                    submission_id: row.get("submission_id"),
-                    user_id: row.get("user_id"),
-                    problem_id: row.get("problem_id"),
-                    submission_content: submisssion_content_file,
-                    submission_answer_code: row.get("submission_answer_code"),
-                });
+    if let Some(cursor) = &params.cursor {
+        let (key, id) = parse_cursor(cursor, key_column.is_some()).ok_or(StatusCode::BAD_REQUEST)?;
+        match key_column {
+            Some(column) => {
+                query
+                    .push(format!(" AND ({}, S.SUBMISSION_ID) > (", column))
+                    .push_bind(key)
+                    .push(", ")
+                    .push_bind(id)
+                    .push(")");
+            }
+            None if params.sort == SubmissionSort::Newest => {
+                query.push(" AND S.SUBMISSION_ID < ").push_bind(id);
+            }
+            None => {
+                query.push(" AND S.SUBMISSION_ID > ").push_bind(id);
             }
-
-            Ok((StatusCode::OK, Json(submissions)))
         }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
+
+    match key_column {
+        Some(column) => query.push(format!(" ORDER BY {} ASC, S.SUBMISSION_ID ASC", column)),
+        None if params.sort == SubmissionSort::Newest => query.push(" ORDER BY S.SUBMISSION_ID DESC"),
+        None => query.push(" ORDER BY S.SUBMISSION_ID ASC"),
+    };
+
+    // One extra row tells whether there is a next page.
+    query.push(" LIMIT ").push_bind(limit + 1);
+
+    let mut rows = query
+        .build()
+        .fetch_all(&pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+
+    let mut submissions = Vec::new();
+    for row in &rows {
+        let submission_content = if params.summary {
+            None
+        } else {
+            Some(read_source(&row.get::<String, _>("submission_url")).await)
+        };
+
+        submissions.push(to_submission(row, submission_content));
+    }
+
+    let next_cursor = if has_more {
+        submissions.last().map(|submission| match params.sort {
+            SubmissionSort::Fastest => format!("{}:{}", submission.submission_time.unwrap_or(0), submission.submission_id),
+            SubmissionSort::Lightest => format!("{}:{}", submission.submission_memory.unwrap_or(0), submission.submission_id),
+            SubmissionSort::Oldest | SubmissionSort::Newest => submission.submission_id.to_string(),
+        })
+    } else {
+        None
+    };
+
+    Ok((StatusCode::OK, Json(SubmissionPage { submissions, next_cursor })))
 }
 
 
 pub async fn get_submission_by_id(
+    AuthenticatedUser(claims): AuthenticatedUser,
     Extension(pool): Extension<PgPool>,
     Path(id): Path<i32>
-) -> Result<(StatusCode, Json<Submission>), StatusCode> {
+) -> Result<(StatusCode, Json<SubmissionDetail>), StatusCode> {
     let query = "
         SELECT
             submission_id,
             user_id,
             problem_id,
             submission_url,
-            submission_answer_code
+            submission_answer_code,
+            submission_earned_score,
+            submission_language,
+            submission_time,
+            submission_memory,
+            submission_created_at,
+            submission_judged_at,
+            submission_compile_output,
+            submission_file_name
         FROM submissions
         WHERE submission_id = $1
         ORDER BY submission_id ASC
@@ -78,11 +200,15 @@ pub async fn get_submission_by_id(
 
     let row = sqlx::query(query)
         .bind(id)
-        .fetch_one(&pool)
+        .fetch_optional(&pool)
         .await;
 
     match row {
-        Ok(row) => {
+        Ok(Some(row)) => {
+            if !can_view(&claims, row.get("user_id")) {
+                return Err(StatusCode::FORBIDDEN);
+            }
+
             let submission_path: String = row.get("submission_url");
 
             let submisssion_content_file = match read_to_string(&submission_path).await {
@@ -90,68 +216,148 @@ pub async fn get_submission_by_id(
                 Err(_) => String::from("[Error al leer el envío]"),
             };
 
-            let submission = Submission {
-                submission_id: row.get("submission_id"),
-                user_id: row.get("user_id"),
-                problem_id: row.get("problem_id"),
-                submission_content: submisssion_content_file,
-                submission_answer_code: row.get("submission_answer_code"),
-            };
-            
+            let submission = to_submission(&row, Some(submisssion_content_file));
+
+            let mut files: Vec<String> = row.get::<Option<String>, _>("submission_file_name").into_iter().collect();
+            files.extend(
+                sqlx::query("SELECT FILE_NAME FROM SUBMISSION_FILES WHERE SUBMISSION_ID = $1 ORDER BY FILE_ORDER ASC")
+                    .bind(id)
+                    .fetch_all(&pool)
+                    .await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                    .iter()
+                    .map(|file| file.get::<String, _>("file_name")),
+            );
+
+            let results = get_test_case_results(&pool, id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-            Ok((StatusCode::OK, Json(submission)))
+            let subtasks = get_subtask_results(&pool, id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            Ok((StatusCode::OK, Json(SubmissionDetail {
+                submission,
+                files,
+                compile_output: row.get("submission_compile_output"),
+                results,
+                subtasks,
+            })))
         }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
+/// `GET /?user_id={id}` under another path: same filters, order and pages.
 pub async fn get_submissions_by_user_id(
+    AuthenticatedUser(claims): AuthenticatedUser,
     Extension(pool): Extension<PgPool>,
-    Path(id): Path<i32>
-) -> Result<(StatusCode, Json<Vec<Submission>>), StatusCode> {
-    let query = "
-        SELECT
-            submission_id,
-            user_id,
-            problem_id,
-            submission_url,
-            submission_answer_code
-        FROM submissions
-        WHERE user_id = $1
-        ORDER BY submission_id ASC
-    ";
-
-    let rows = sqlx::query(query).bind(id).fetch_all(&pool).await;
+    Path(id): Path<i32>,
+    Query(params): Query<SubmissionQuery>,
+) -> Result<(StatusCode, Json<SubmissionPage>), StatusCode> {
+    if !can_view(&claims, id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
 
-    match rows {
-        Ok(rows) => {
-            let mut submissions = Vec::new();
+    let params = SubmissionQuery { user_id: Some(id), ..params };
 
-            for row in rows {
-                let submission_path: String = row.get("submission_url");
+    get_submissions(AuthenticatedUser(claims), Extension(pool), Query(params)).await
+}
 
-                let submisssion_content_file = match read_to_string(&submission_path).await {
-                    Ok(content) => content,
-                    Err(_) => String::from("[Error al leer el envío]"),
-                };
+fn to_submission(row: &PgRow, submission_content: Option<String>) -> Submission {
+    Submission {
+        submission_id: row.get("submission_id"),
+        user_id: row.get("user_id"),
+        problem_id: row.get("problem_id"),
+        submission_content,
+        submission_answer_code: row.get("submission_answer_code"),
+        submission_earned_score: row.get("submission_earned_score"),
+        submission_language: row.get("submission_language"),
+        submission_time: row.get("submission_time"),
+        submission_memory: row.get("submission_memory"),
+        submission_created_at: row.get("submission_created_at"),
+        submission_judged_at: row.get("submission_judged_at"),
+    }
+}
 
-                submissions.push(Submission {
-                    submission_id: row.get("submission_id"),
-                    user_id: row.get("user_id"),
-                    problem_id: row.get("problem_id"),
-                    submission_content: submisssion_content_file,
-                    submission_answer_code: row.get("submission_answer_code"),
-                });
-            }
+fn sort_key_column(sort: SubmissionSort) -> Option<&'static str> {
+    match sort {
+        SubmissionSort::Fastest => Some("S.SUBMISSION_TIME"),
+        SubmissionSort::Lightest => Some("S.SUBMISSION_MEMORY"),
+        SubmissionSort::Oldest | SubmissionSort::Newest => None,
+    }
+}
 
-            Ok((StatusCode::OK, Json(submissions)))
-        }
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+/// Cursors are `{id}` for id order and `{key}:{id}` when sorting by a key.
+fn parse_cursor(cursor: &str, keyed: bool) -> Option<(i32, i32)> {
+    if keyed {
+        let (key, id) = cursor.split_once(':')?;
+        Some((key.parse().ok()?, id.parse().ok()?))
+    } else {
+        Some((0, cursor.parse().ok()?))
     }
 }
 
+async fn get_test_case_results(pool: &PgPool, submission_id: i32) -> Result<Vec<TestCaseResult>, sqlx::Error> {
+    let query = "
+        SELECT
+            result_test_case,
+            result_verdict,
+            result_wall_time,
+            result_memory,
+            result_signal,
+            result_exit_code,
+            result_stderr
+        FROM submission_results
+        WHERE submission_id = $1
+        ORDER BY result_order ASC
+    ";
 
+    let rows = sqlx::query(query).bind(submission_id).fetch_all(pool).await?;
 
+    Ok(rows
+        .into_iter()
+        .map(|row| TestCaseResult {
+            test_case: row.get("result_test_case"),
+            veredict: row.get("result_verdict"),
+            wall_time: row.get("result_wall_time"),
+            memory: row.get("result_memory"),
+            signal: row.get("result_signal"),
+            exit_code: row.get("result_exit_code"),
+            stderr: row.get("result_stderr"),
+        })
+        .collect())
+}
 
+async fn get_subtask_results(pool: &PgPool, submission_id: i32) -> Result<Vec<SubtaskResult>, sqlx::Error> {
+    let query = "
+        SELECT
+            subtask_id,
+            subtask_verdict,
+            subtask_score,
+            subtask_earned_score
+        FROM submission_subtask_scores
+        WHERE submission_id = $1
+        ORDER BY subtask_order ASC
+    ";
+
+    let rows = sqlx::query(query).bind(submission_id).fetch_all(pool).await?;
 
+    Ok(rows
+        .into_iter()
+        .map(|row| SubtaskResult {
+            subtask_id: row.get("subtask_id"),
+            veredict: row.get("subtask_verdict"),
+            score: row.get("subtask_score"),
+            earned_score: row.get("subtask_earned_score"),
+        })
+        .collect())
+}
 
+async fn read_source(submission_path: &str) -> String {
+    read_to_string(submission_path)
+        .await
+        .unwrap_or_else(|_| String::from("[Error al leer el envío]"))
+}
@@ -0,0 +1,17 @@
+use axum::{
+    extract::Extension,
+    http::StatusCode,
+    Json,
+};
+use sqlx::PgPool;
+use crate::judge::languages::list_languages;
+use crate::models::language_models::Language;
+
+pub async fn get_languages(
+    Extension(pool): Extension<PgPool>,
+) -> Result<(StatusCode, Json<Vec<Language>>), StatusCode> {
+    match list_languages(&pool, false).await {
+        Ok(languages) => Ok((StatusCode::OK, Json(languages))),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -1,3 +1,9 @@
 pub mod upload_code_handler;
 pub mod get_submissions_handler;
-pub mod get_attemps_handler;
\ No newline at end of file
+pub mod get_attemps_handler;
+pub mod rejudge_handler;
+pub mod languages_handler;
+pub mod status_handler;
+pub mod run_handler;
+pub mod plagiarism_handler;
+pub mod source_handler;
@@ -0,0 +1,372 @@
+use std::collections::BTreeMap;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use crate::judge::worker::load_submission_files;
+use crate::models::plagiarism_models::{MatchRegion, PairDetail, PairSummary, PlagiarismReport, PlagiarismRequest};
+use crate::plagiarism::{find_suspicious_pairs, Document, SuspiciousPair};
+use crate::utils::auth::{professor_id, AuthenticatedUser};
+
+const DEFAULT_THRESHOLD: f64 = 0.5;
+
+/// Compares the last accepted submission of every user on a problem (or on
+/// every problem of a contest) and stores the pairs above the threshold.
+pub async fn create_plagiarism_report(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Json(payload): Json<PlagiarismRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    let professor_id = professor_id(&claims)?;
+
+    if payload.problem_id.is_some() == payload.contest_id.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Give either a problem_id or a contest_id" }))
+        ));
+    }
+
+    let threshold = payload.threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "threshold must be between 0 and 1" }))
+        ));
+    }
+
+    let query = "
+        SELECT DISTINCT ON (S.USER_ID, S.PROBLEM_ID)
+            S.SUBMISSION_ID,
+            S.USER_ID,
+            S.PROBLEM_ID,
+            S.SUBMISSION_LANGUAGE,
+            S.SUBMISSION_URL
+        FROM SUBMISSIONS S
+        WHERE S.SUBMISSION_ANSWER_CODE = 'AC'
+        AND ($1::INT IS NULL OR S.PROBLEM_ID = $1)
+        AND ($2::INT IS NULL OR S.PROBLEM_ID IN (SELECT PROBLEM_ID FROM PROBLEM_SETS WHERE CONTEST_ID = $2))
+        ORDER BY S.USER_ID, S.PROBLEM_ID, S.SUBMISSION_ID DESC
+    ";
+
+    let rows = sqlx::query(query)
+        .bind(payload.problem_id)
+        .bind(payload.contest_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(db_error)?;
+
+    let mut by_problem: BTreeMap<i32, Vec<Document>> = BTreeMap::new();
+    let mut compared = 0;
+
+    for row in rows {
+        let submission_id: i32 = row.get("submission_id");
+        let submission_url: String = row.get("submission_url");
+
+        let source = match load_source(&pool, submission_id, submission_url).await {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Cannot read submission {} for plagiarism: {}", submission_id, e);
+                continue;
+            }
+        };
+
+        let hash_comments = row.get::<String, _>("submission_language") == "python";
+        by_problem
+            .entry(row.get("problem_id"))
+            .or_default()
+            .push(Document::new(submission_id, row.get("user_id"), &source, hash_comments));
+        compared += 1;
+    }
+
+    let pairs: Vec<SuspiciousPair> = tokio::task::spawn_blocking(move || {
+        by_problem
+            .values()
+            .flat_map(|documents| find_suspicious_pairs(documents, threshold))
+            .collect()
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("Plagiarism check failed: {}", e) }))
+        )
+    })?;
+
+    let report_id = save_report(&pool, &payload, threshold, compared, professor_id, &pairs)
+        .await
+        .map_err(db_error)?;
+
+    let report = load_report(&pool, report_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| db_error(sqlx::Error::RowNotFound))?;
+
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+pub async fn get_plagiarism_report(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Path(report_id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    professor_id(&claims)?;
+
+    let report = load_report(&pool, report_id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Report not found!" }))
+            )
+        })?;
+
+    Ok((StatusCode::OK, Json(report)))
+}
+
+/// Both sources of a suspicious pair with the matching line ranges.
+pub async fn get_plagiarism_pair(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Path(pair_id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    professor_id(&claims)?;
+
+    let query = "
+        SELECT
+            P.PAIR_ID,
+            A.PROBLEM_ID,
+            P.SUBMISSION_A,
+            A.USER_ID AS USER_A,
+            A.SUBMISSION_URL AS URL_A,
+            P.SUBMISSION_B,
+            B.USER_ID AS USER_B,
+            B.SUBMISSION_URL AS URL_B,
+            P.PAIR_SIMILARITY
+        FROM PLAGIARISM_PAIRS P
+        JOIN SUBMISSIONS A ON A.SUBMISSION_ID = P.SUBMISSION_A
+        JOIN SUBMISSIONS B ON B.SUBMISSION_ID = P.SUBMISSION_B
+        WHERE P.PAIR_ID = $1
+    ";
+
+    let row = sqlx::query(query)
+        .bind(pair_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Pair not found!" }))
+            )
+        })?;
+
+    let region_query = "
+        SELECT
+            REGION_A_START_LINE,
+            REGION_A_END_LINE,
+            REGION_B_START_LINE,
+            REGION_B_END_LINE
+        FROM PLAGIARISM_REGIONS
+        WHERE PAIR_ID = $1
+        ORDER BY REGION_ORDER ASC
+    ";
+
+    let regions = sqlx::query(region_query)
+        .bind(pair_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|region| MatchRegion {
+            a_start_line: region.get("region_a_start_line"),
+            a_end_line: region.get("region_a_end_line"),
+            b_start_line: region.get("region_b_start_line"),
+            b_end_line: region.get("region_b_end_line"),
+        })
+        .collect();
+
+    let source_a = load_source(&pool, row.get("submission_a"), row.get("url_a")).await;
+    let source_b = load_source(&pool, row.get("submission_b"), row.get("url_b")).await;
+
+    let detail = PairDetail {
+        pair: to_pair_summary(&row),
+        source_a: source_a.unwrap_or_else(|_| String::from("[Error al leer el envío]")),
+        source_b: source_b.unwrap_or_else(|_| String::from("[Error al leer el envío]")),
+        regions,
+    };
+
+    Ok((StatusCode::OK, Json(detail)))
+}
+
+/// Every file of the submission joined in upload order. The region lines of a
+/// pair point into this text, so the pair detail shows it too.
+async fn load_source(pool: &PgPool, submission_id: i32, submission_url: String) -> Result<String, String> {
+    let files = load_submission_files(pool, submission_id, submission_url, None).await?;
+
+    Ok(files
+        .into_iter()
+        .map(|file| file.content)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+async fn save_report(
+    pool: &PgPool,
+    request: &PlagiarismRequest,
+    threshold: f64,
+    compared: i32,
+    professor_id: i32,
+    pairs: &[SuspiciousPair],
+) -> Result<i32, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let report_query = "
+        INSERT INTO PLAGIARISM_REPORTS(
+            PROBLEM_ID,
+            CONTEST_ID,
+            REPORT_THRESHOLD,
+            REPORT_SUBMISSIONS,
+            REPORT_REQUESTED_BY
+        )
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING REPORT_ID
+    ";
+
+    let report_id: i32 = sqlx::query(report_query)
+        .bind(request.problem_id)
+        .bind(request.contest_id)
+        .bind(threshold)
+        .bind(compared)
+        .bind(professor_id)
+        .fetch_one(&mut *tx)
+        .await?
+        .get("report_id");
+
+    let pair_query = "
+        INSERT INTO PLAGIARISM_PAIRS(
+            REPORT_ID,
+            SUBMISSION_A,
+            SUBMISSION_B,
+            PAIR_SIMILARITY
+        )
+        VALUES ($1, $2, $3, $4)
+        RETURNING PAIR_ID
+    ";
+
+    let region_query = "
+        INSERT INTO PLAGIARISM_REGIONS(
+            PAIR_ID,
+            REGION_ORDER,
+            REGION_A_START_LINE,
+            REGION_A_END_LINE,
+            REGION_B_START_LINE,
+            REGION_B_END_LINE
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+    ";
+
+    for pair in pairs {
+        let pair_id: i32 = sqlx::query(pair_query)
+            .bind(report_id)
+            .bind(pair.submission_a)
+            .bind(pair.submission_b)
+            .bind(pair.similarity)
+            .fetch_one(&mut *tx)
+            .await?
+            .get("pair_id");
+
+        for (order, region) in pair.regions.iter().enumerate() {
+            sqlx::query(region_query)
+                .bind(pair_id)
+                .bind(order as i32 + 1)
+                .bind(region.a_start_line)
+                .bind(region.a_end_line)
+                .bind(region.b_start_line)
+                .bind(region.b_end_line)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(report_id)
+}
+
+async fn load_report(pool: &PgPool, report_id: i32) -> Result<Option<PlagiarismReport>, sqlx::Error> {
+    let report_query = "
+        SELECT
+            REPORT_ID,
+            PROBLEM_ID,
+            CONTEST_ID,
+            REPORT_THRESHOLD,
+            REPORT_SUBMISSIONS,
+            REPORT_REQUESTED_BY,
+            REPORT_CREATED_AT
+        FROM PLAGIARISM_REPORTS
+        WHERE REPORT_ID = $1
+    ";
+
+    let Some(row) = sqlx::query(report_query).bind(report_id).fetch_optional(pool).await? else {
+        return Ok(None);
+    };
+
+    let pairs_query = "
+        SELECT
+            P.PAIR_ID,
+            A.PROBLEM_ID,
+            P.SUBMISSION_A,
+            A.USER_ID AS USER_A,
+            P.SUBMISSION_B,
+            B.USER_ID AS USER_B,
+            P.PAIR_SIMILARITY
+        FROM PLAGIARISM_PAIRS P
+        JOIN SUBMISSIONS A ON A.SUBMISSION_ID = P.SUBMISSION_A
+        JOIN SUBMISSIONS B ON B.SUBMISSION_ID = P.SUBMISSION_B
+        WHERE P.REPORT_ID = $1
+        ORDER BY P.PAIR_SIMILARITY DESC, P.PAIR_ID ASC
+    ";
+
+    let pairs = sqlx::query(pairs_query)
+        .bind(report_id)
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(to_pair_summary)
+        .collect();
+
+    Ok(Some(PlagiarismReport {
+        report_id: row.get("report_id"),
+        problem_id: row.get("problem_id"),
+        contest_id: row.get("contest_id"),
+        threshold: row.get("report_threshold"),
+        submissions: row.get("report_submissions"),
+        requested_by: row.get("report_requested_by"),
+        created_at: row.get("report_created_at"),
+        pairs,
+    }))
+}
+
+fn to_pair_summary(row: &sqlx::postgres::PgRow) -> PairSummary {
+    PairSummary {
+        pair_id: row.get("pair_id"),
+        problem_id: row.get("problem_id"),
+        submission_a: row.get("submission_a"),
+        user_a: row.get("user_a"),
+        submission_b: row.get("submission_b"),
+        user_b: row.get("user_b"),
+        similarity: row.get("pair_similarity"),
+    }
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("Database error: {}", e) }))
+    )
+}
@@ -0,0 +1,138 @@
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use crate::judge::queue::{self, JudgeQueue};
+use crate::judge::status::StatusBroadcaster;
+use crate::models::job_models::RejudgeFilter;
+use crate::models::result_models::VerdictChange;
+use crate::utils::auth::{professor_id, AuthenticatedUser};
+
+pub async fn rejudge_submission(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Extension(judge_queue): Extension<JudgeQueue>,
+    Extension(status): Extension<StatusBroadcaster>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    let professor_id = professor_id(&claims)?;
+
+    let filter = RejudgeFilter {
+        submission_id: Some(id),
+        problem_id: None,
+        from: None,
+        to: None,
+    };
+
+    rejudge(&pool, &judge_queue, &status, &filter, professor_id).await
+}
+
+pub async fn rejudge_submissions(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Extension(judge_queue): Extension<JudgeQueue>,
+    Extension(status): Extension<StatusBroadcaster>,
+    Json(filter): Json<RejudgeFilter>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    let professor_id = professor_id(&claims)?;
+
+    if filter.submission_id.is_none() && filter.problem_id.is_none() && filter.from.is_none() && filter.to.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Give a submission_id, a problem_id or a from/to time range" }))
+        ));
+    }
+
+    if let (Some(from), Some(to)) = (filter.from, filter.to)
+        && from >= to
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "from must be before to" }))
+        ));
+    }
+
+    rejudge(&pool, &judge_queue, &status, &filter, professor_id).await
+}
+
+pub async fn get_verdict_history(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    professor_id(&claims)?;
+
+    let query = "
+        SELECT
+            H.JOB_ID,
+            J.JOB_REQUESTED_BY,
+            H.HISTORY_PREVIOUS_VERDICT,
+            H.HISTORY_PREVIOUS_SCORE,
+            H.HISTORY_VERDICT,
+            H.HISTORY_SCORE,
+            H.HISTORY_CREATED_AT
+        FROM SUBMISSION_VERDICT_HISTORY H
+        LEFT JOIN JUDGE_JOBS J ON J.JOB_ID = H.JOB_ID
+        WHERE H.SUBMISSION_ID = $1
+        ORDER BY H.HISTORY_ID ASC
+    ";
+
+    let rows = sqlx::query(query)
+        .bind(id)
+        .fetch_all(&pool)
+        .await
+        .map_err(db_error)?;
+
+    let history: Vec<VerdictChange> = rows
+        .into_iter()
+        .map(|row| VerdictChange {
+            job_id: row.get("job_id"),
+            requested_by: row.get("job_requested_by"),
+            previous_veredict: row.get("history_previous_verdict"),
+            previous_score: row.get("history_previous_score"),
+            veredict: row.get("history_verdict"),
+            score: row.get("history_score"),
+            changed_at: row.get("history_created_at"),
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(history)))
+}
+
+async fn rejudge(
+    pool: &PgPool,
+    judge_queue: &JudgeQueue,
+    status: &StatusBroadcaster,
+    filter: &RejudgeFilter,
+    professor_id: i32,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    let queued = queue::enqueue_rejudge(pool, filter, professor_id)
+        .await
+        .map_err(db_error)?;
+
+    let mut submission_ids = Vec::new();
+    for (submission_id, user_id) in queued {
+        status.queued(submission_id, user_id);
+        judge_queue.wake_worker();
+        submission_ids.push(submission_id);
+    }
+
+    let body = json!({
+        "status": true,
+        "queued": submission_ids.len(),
+        "submission_ids": submission_ids,
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(body)))
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("Database error: {}", e) }))
+    )
+}
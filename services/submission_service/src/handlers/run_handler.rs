@@ -0,0 +1,101 @@
+use axum::{
+    extract::Extension,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::{PgPool, Row};
+use crate::judge::client::is_unavailable;
+use crate::judge::languages::get_language;
+use crate::judge::runner::Runner;
+use crate::models::compile_models::{CompileStruct, SourceFile};
+use crate::models::run_models::RunRequest;
+use crate::utils::auth::AuthenticatedUser;
+
+const DEFAULT_TIME_MS: i32 = 2000;
+const DEFAULT_MEMORY_MB: i32 = 256;
+const MAX_STDIN_BYTES: usize = 1024 * 1024;
+
+pub async fn run_code(
+    _user: AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Extension(runner): Extension<Runner>,
+    Json(payload): Json<RunRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    if payload.source.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Missing field: source" }))
+        ));
+    }
+
+    if payload.stdin.len() > MAX_STDIN_BYTES {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({ "error": "Max 1MB of input" }))
+        ));
+    }
+
+    let language = get_language(&pool, &payload.lang)
+        .await
+        .map_err(db_error)?
+        .filter(|language| language.enabled)
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "language not valid" }))
+            )
+        })?;
+
+    let (time_limit, memory_limit) = match payload.problem_id {
+        Some(problem_id) => {
+            let row = sqlx::query("SELECT PROBLEM_TIME_MS_LIMIT, PROBLEM_MEMORY_MB_LIMIT FROM PROBLEMS WHERE PROBLEM_ID = $1")
+                .bind(problem_id)
+                .fetch_optional(&pool)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({ "error": "Problem not found!" }))
+                    )
+                })?;
+
+            (row.get("problem_time_ms_limit"), row.get("problem_memory_mb_limit"))
+        }
+        None => (DEFAULT_TIME_MS, DEFAULT_MEMORY_MB),
+    };
+
+    let source = CompileStruct {
+        lang: language.name.clone(),
+        version: language.version.clone(),
+        files: vec![SourceFile { name: None, content: payload.source }],
+        memory_limit: language.memory_limit(memory_limit),
+        time_limit: language.time_limit(time_limit),
+    };
+
+    if !runner.is_available() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "Judge unavailable, try again later" }))
+        ));
+    }
+
+    let result = runner.run(source, &payload.stdin).await.map_err(|e| {
+        let status = if is_unavailable(&e) { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::BAD_GATEWAY };
+        (
+            status,
+            Json(json!({ "error": format!("Judge error: {}", e) }))
+        )
+    })?;
+
+    Ok((StatusCode::OK, Json(result)))
+}
+
+fn db_error(e: sqlx::Error) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("DB error: {}", e) }))
+    )
+}
@@ -0,0 +1,173 @@
+use std::path::Path as FilePath;
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use similar::TextDiff;
+use sqlx::{PgPool, Row};
+use tokio::fs::read_to_string;
+use crate::models::auth_models::Claims;
+use crate::utils::auth::{can_view, AuthenticatedUser};
+
+const DEFAULT_DIFF_CONTEXT: usize = 3;
+
+#[derive(Deserialize)]
+pub struct DiffQuery {
+    a: i32,
+    b: i32,
+    #[serde(default)]
+    context: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct SourceQuery {
+    #[serde(default)]
+    file: Option<String>,
+}
+
+struct StoredSource {
+    user_id: i32,
+    problem_id: i32,
+    file_name: String,
+    content: String,
+}
+
+/// The submitted file as it was uploaded, named after the submission. The
+/// other files of a multi-file submission are picked with `file`.
+pub async fn get_submission_source(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Path(id): Path<i32>,
+    Query(query): Query<SourceQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    let source = load_source(&pool, &claims, id, query.file.as_deref()).await?;
+    let extension = FilePath::new(&source.file_name).extension().and_then(|e| e.to_str()).unwrap_or("");
+
+    let headers = [
+        (header::CONTENT_TYPE, content_type(extension).to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", source.file_name)),
+    ];
+
+    Ok((StatusCode::OK, headers, source.content))
+}
+
+/// Unified diff from submission `a` to submission `b`. Only professors can
+/// compare submissions of different users or problems.
+pub async fn diff_submissions(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Query(query): Query<DiffQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    let a = load_source(&pool, &claims, query.a, None).await?;
+    let b = load_source(&pool, &claims, query.b, None).await?;
+
+    if !claims.is_professor() && (a.user_id != b.user_id || a.problem_id != b.problem_id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Only submissions of the same user and problem can be compared" }))
+        ));
+    }
+
+    let diff = TextDiff::from_lines(&a.content, &b.content)
+        .unified_diff()
+        .context_radius(query.context.unwrap_or(DEFAULT_DIFF_CONTEXT))
+        .header(&a.file_name, &b.file_name)
+        .to_string();
+
+    let body = json!({
+        "a": query.a,
+        "b": query.b,
+        "diff": diff,
+    });
+
+    Ok((StatusCode::OK, Json(body)))
+}
+
+async fn load_source(pool: &PgPool, claims: &Claims, id: i32, file: Option<&str>) -> Result<StoredSource, (StatusCode, Json<Value>)> {
+    let row = sqlx::query("SELECT USER_ID, PROBLEM_ID, SUBMISSION_URL, SUBMISSION_FILE_NAME FROM SUBMISSIONS WHERE SUBMISSION_ID = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Database error: {}", e) }))
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": format!("Submission {} not found!", id) }))
+            )
+        })?;
+
+    let user_id: i32 = row.get("user_id");
+    if !can_view(claims, user_id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": format!("Submission {} belongs to another user", id) }))
+        ));
+    }
+
+    let main_file: Option<String> = row.get("submission_file_name");
+
+    let (url, file_name) = match file {
+        Some(name) if main_file.as_deref() != Some(name) => {
+            let url: String = sqlx::query("SELECT FILE_URL FROM SUBMISSION_FILES WHERE SUBMISSION_ID = $1 AND FILE_NAME = $2")
+                .bind(id)
+                .bind(name)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({ "error": format!("Database error: {}", e) }))
+                    )
+                })?
+                .ok_or_else(|| {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({ "error": format!("Submission {} has no file {}", id, name) }))
+                    )
+                })?
+                .get("file_url");
+            (url, name.to_string())
+        }
+        _ => {
+            let url: String = row.get("submission_url");
+            let file_name = match FilePath::new(&url).extension().and_then(|e| e.to_str()) {
+                Some(extension) => format!("submission_{}.{}", id, extension),
+                None => format!("submission_{}", id),
+            };
+            (url, file_name)
+        }
+    };
+
+    let content = read_to_string(&url).await.map_err(|_| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("Source of submission {} is missing", id) }))
+        )
+    })?;
+
+    Ok(StoredSource {
+        user_id,
+        problem_id: row.get("problem_id"),
+        file_name,
+        content,
+    })
+}
+
+fn content_type(extension: &str) -> &'static str {
+    match extension {
+        "cpp" => "text/x-c++src; charset=utf-8",
+        "c" => "text/x-csrc; charset=utf-8",
+        "py" => "text/x-python; charset=utf-8",
+        "java" => "text/x-java-source; charset=utf-8",
+        _ => "text/plain; charset=utf-8",
+    }
+}
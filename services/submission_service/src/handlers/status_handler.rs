@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse},
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use sqlx::{PgPool, Row};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use crate::judge::status::StatusBroadcaster;
+use crate::models::status_models::StatusEvent;
+use crate::utils::auth::{can_view, EventStreamUser};
+
+#[derive(Clone, Copy)]
+enum StatusFilter {
+    Submission(i32),
+    User(i32),
+}
+
+impl StatusFilter {
+    fn matches(&self, event: &StatusEvent) -> bool {
+        match self {
+            StatusFilter::Submission(id) => event.submission_id == *id,
+            StatusFilter::User(id) => event.user_id == *id,
+        }
+    }
+}
+
+// Where each submission stands: the status of its pending job if it has one,
+// its verdict otherwise.
+const STATE_QUERY: &str = "
+    SELECT
+        S.SUBMISSION_ID,
+        S.USER_ID,
+        S.SUBMISSION_ANSWER_CODE,
+        S.SUBMISSION_EARNED_SCORE,
+        (
+            SELECT J.JOB_STATUS
+            FROM JUDGE_JOBS J
+            WHERE J.SUBMISSION_ID = S.SUBMISSION_ID
+            AND J.JOB_STATUS IN ('QUEUED', 'RUNNING')
+            ORDER BY J.JOB_ID DESC
+            LIMIT 1
+        ) AS ACTIVE_JOB_STATUS
+    FROM SUBMISSIONS S
+";
+
+// Clock skew allowed between this replica and the database when catching up.
+const CATCH_UP_MARGIN_SECS: i64 = 5;
+
+/// Streams the judging progress of one submission, starting with its current
+/// state, and closes once the final verdict is sent.
+pub async fn get_submission_events(
+    EventStreamUser(claims): EventStreamUser,
+    Extension(pool): Extension<PgPool>,
+    Extension(status): Extension<StatusBroadcaster>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, StatusCode> {
+    // Subscribe first so nothing is lost between the snapshot and the stream.
+    let receiver = status.subscribe();
+
+    let current = current_states(&pool, StatusFilter::Submission(id), Utc::now())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .pop()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !can_view(&claims, current.user_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(event_response(receiver, pool, StatusFilter::Submission(id), Some(current), true))
+}
+
+pub async fn get_user_events(
+    EventStreamUser(claims): EventStreamUser,
+    Extension(pool): Extension<PgPool>,
+    Extension(status): Extension<StatusBroadcaster>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if !can_view(&claims, id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(event_response(status.subscribe(), pool, StatusFilter::User(id), None, false))
+}
+
+/// The current state of the submissions behind `filter`. For a user these are
+/// the ones still being judged and the ones finished since `since`.
+async fn current_states(pool: &PgPool, filter: StatusFilter, since: DateTime<Utc>) -> Result<Vec<StatusEvent>, sqlx::Error> {
+    let rows = match filter {
+        StatusFilter::Submission(id) => {
+            sqlx::query(&format!("{} WHERE S.SUBMISSION_ID = $1", STATE_QUERY))
+                .bind(id)
+                .fetch_all(pool)
+                .await?
+        }
+        StatusFilter::User(id) => {
+            let query = format!(
+                "{} WHERE S.USER_ID = $1
+                AND EXISTS (
+                    SELECT 1
+                    FROM JUDGE_JOBS J
+                    WHERE J.SUBMISSION_ID = S.SUBMISSION_ID
+                    AND (J.JOB_STATUS IN ('QUEUED', 'RUNNING') OR J.JOB_FINISHED_AT >= $2)
+                )
+                ORDER BY S.SUBMISSION_ID ASC",
+                STATE_QUERY
+            );
+            sqlx::query(&query)
+                .bind(id)
+                .bind(since - chrono::Duration::seconds(CATCH_UP_MARGIN_SECS))
+                .fetch_all(pool)
+                .await?
+        }
+    };
+
+    let states = rows
+        .into_iter()
+        .map(|row| {
+            let id: i32 = row.get("submission_id");
+            let user_id: i32 = row.get("user_id");
+            match row.get::<Option<String>, _>("active_job_status") {
+                Some(job_status) => StatusEvent::new(id, user_id, &job_status),
+                None => StatusEvent {
+                    veredict: Some(row.get("submission_answer_code")),
+                    score: Some(row.get("submission_earned_score")),
+                    ..StatusEvent::new(id, user_id, "FINISHED")
+                },
+            }
+        })
+        .collect();
+
+    Ok(states)
+}
+
+fn event_response(receiver: Receiver<StatusEvent>, pool: PgPool, filter: StatusFilter, current: Option<StatusEvent>, close_when_final: bool) -> impl IntoResponse {
+    let stream = EventStream {
+        receiver,
+        pool,
+        filter,
+        pending: current.into_iter().collect(),
+        since: Utc::now(),
+        close_when_final,
+        done: false,
+    };
+
+    // Tells nginx not to buffer the stream.
+    (
+        [("X-Accel-Buffering", "no")],
+        Sse::new(stream.into_stream()).keep_alive(KeepAlive::default()),
+    )
+}
+
+struct EventStream {
+    receiver: Receiver<StatusEvent>,
+    pool: PgPool,
+    filter: StatusFilter,
+    pending: VecDeque<StatusEvent>,
+    // When the last event came in, to know what a lagging stream missed.
+    since: DateTime<Utc>,
+    close_when_final: bool,
+    done: bool,
+}
+
+impl EventStream {
+    fn into_stream(self) -> impl Stream<Item = Result<Event, Infallible>> {
+        stream::unfold(self, |mut state| async move {
+            if state.done {
+                return None;
+            }
+
+            let event = state.next_event().await?;
+
+            state.done = state.close_when_final && event.is_final();
+            let sse_event = Event::default()
+                .event(event.state.to_lowercase())
+                .json_data(&event)
+                .unwrap_or_default();
+
+            Some((Ok(sse_event), state))
+        })
+    }
+
+    async fn next_event(&mut self) -> Option<StatusEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            match self.receiver.recv().await {
+                Ok(event) if self.filter.matches(&event) => {
+                    self.since = Utc::now();
+                    return Some(event);
+                }
+                Ok(_) => continue,
+                // Skipped events are gone; the database has where they led.
+                Err(RecvError::Lagged(_)) => {
+                    let since = self.since;
+                    self.since = Utc::now();
+                    match current_states(&self.pool, self.filter, since).await {
+                        Ok(states) => self.pending.extend(states),
+                        Err(e) => eprintln!("Cannot catch up a status stream: {}", e),
+                    }
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
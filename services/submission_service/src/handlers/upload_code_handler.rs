@@ -1,39 +1,43 @@
 use std::path::Path;
-use axum::{extract:: {Extension, Multipart}, response::IntoResponse, Json};
-use sqlx::{PgPool, Row};
-use mime_guess::from_path;
-use serde_json::json;
-use tokio::{fs::{self, File}, io::AsyncWriteExt};
+use axum::{extract:: {Extension, Multipart}, http::header, response::{IntoResponse, Response}, Json};
+use sha2::{Digest, Sha256};
+use sqlx::{postgres::PgRow, PgPool, Row};
+use serde_json::{json, Value};
+use tokio::{fs::File, io::AsyncWriteExt};
 use reqwest::StatusCode;
-use reqwest::Client;
 use uuid::Uuid;
-use std::collections::{HashMap, HashSet};
-use crate::models::compile_models::{CompileStruct, CompileRequest, CompileResponse, SourceFile};
-
-
-pub async fn upload(Extension(pool): Extension<PgPool>, mut multipart: Multipart) -> Result<impl IntoResponse, impl IntoResponse> {
-    let mut user_id:i32 = 0;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use crate::judge::grader::load_grader;
+use crate::judge::languages::get_language;
+use crate::judge::queue::{self, JudgeQueue};
+use crate::judge::status::StatusBroadcaster;
+use crate::models::compile_models::SourceFile;
+use crate::utils::auth::AuthenticatedUser;
+use crate::utils::rate_limit::{take_submission_token, RateLimit, SubmissionLimits};
+
+const MAX_SOURCE_FILES: usize = 16;
+
+pub async fn upload(
+    AuthenticatedUser(claims): AuthenticatedUser,
+    Extension(pool): Extension<PgPool>,
+    Extension(judge_queue): Extension<JudgeQueue>,
+    Extension(status): Extension<StatusBroadcaster>,
+    Extension(limits): Extension<SubmissionLimits>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, impl IntoResponse> {
+    let user_id = claims.user_id().ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Invalid token subject" }))
+        ).into_response()
+    })?;
     let mut problem_id:i32 = 0;
-    let mut upload_path:String = String::new();
     let mut lang:String = String::new();
-    let mut source_code:String = String::new();
+    let mut sources: Vec<SourceFile> = Vec::new();
     let save_path = "/app/submissions";
 
     let mut field_check = HashSet::new();
-    let mut avaliable_langs: HashMap<String, String> = HashMap::new();
-    avaliable_langs.insert("cpp".to_string(), "10.2.0".to_string());
-    avaliable_langs.insert("c".to_string(), "10.2.0".to_string());
-    avaliable_langs.insert("python".to_string(), "3.10.0".to_string());
-    avaliable_langs.insert("java".to_string(), "15.0.2".to_string());
-
-    let judge_ip = std::env::var("JUDGE_IP").map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Judge ip not found!" }))
-        ).into_response()
-    })?;
-
-    let judge_url:String = format!("{}", judge_ip);
 
     while let Some(field) = multipart
         .next_field()
@@ -45,31 +49,6 @@ pub async fn upload(Extension(pool): Extension<PgPool>, mut multipart: Multipart
             ).into_response()
         })?
     {
-        if let Some("user_id") = field.name() {
-            if field_check.contains("user_id") {
-                return Err( (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": "Multiple user id's" }))
-                ).into_response() );
-            }
-            let content = field.text().await.map_err(|_| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": "User id not valid" }))
-                ).into_response()
-            })?;
-            user_id = content.parse().map_err(|_| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": "User id not valid" }))
-                ).into_response()
-            })?;
-
-            field_check.insert("user_id");
-
-            continue;
-        }
-
         if let Some("problem_id") = field.name() {
             if field_check.contains("problem_id") {
                 return Err( (
@@ -107,74 +86,63 @@ pub async fn upload(Extension(pool): Extension<PgPool>, mut multipart: Multipart
                     Json(json!({ "error": "language not valid" }))
                 ).into_response()
             })?;
-        
-            if !avaliable_langs.contains_key(&lang) || lang.is_empty(){
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({ "error": "language not valid" }))
-                ).into_response())
-            }
 
             field_check.insert("lang");
             continue;
         }
 
 
+        // Cada campo `source` es un archivo; el primero es el punto de entrada
         if let Some("source") = field.name(){
+            field_check.insert("source");
+            if sources.len() >= MAX_SOURCE_FILES {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("Max {} source files", MAX_SOURCE_FILES) }))
+                ).into_response());
+            }
 
-            if field_check.contains("source") {
-               return Err( (
-                StatusCode::BAD_REQUEST,
-                Json(json!({ "error": "multiple source files" }))
-            ).into_response() );
+            let Some(file_name) = field.file_name().map(str::to_string) else {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": "source must be a file" }))
+                ).into_response());
+            };
+
+            if !valid_file_name(&file_name) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("Invalid file name: {}", file_name) }))
+                ).into_response());
             }
-            field_check.insert("source");
-            if let Some(file_name) = field.file_name(){
-
-                let extension = from_path(file_name)
-                    .first_raw()
-                    .and_then(|mime| mime.split('/').nth(1))
-                    .unwrap_or("");
-
-
-                let file_name =  if extension.is_empty(){
-                    Uuid::new_v4().to_string()
-                }else{
-                    format!("{}.{}", Uuid::new_v4(), extension)
-                };
-
-                upload_path = format!("{}/{}",save_path,file_name);
-
-                if !Path::new(&save_path).exists(){
-                    tokio::fs::create_dir(&save_path).await.map_err(|e| {
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(json!({ "error": format!("Creating Server: {}", e) }))
-                        ).into_response()
-                    })?;
-                }
-                
-                let bytes = field.bytes().await.map_err(|e| {
-                    (
-                        StatusCode::BAD_REQUEST,
-                        Json(json!({ "error": format!("Parsing to source file: {}", e) }))
-                    ).into_response()
-                })?;
-
-                source_code = String::from_utf8(bytes.to_vec()).map_err(|e| {
-                    (
-                        StatusCode::BAD_REQUEST,
-                        Json(json!({ "error": format!("Parsing to UTF-8 source file: {}", e) }))
-                    ).into_response()
-                })?;
-  
+
+            if sources.iter().any(|source| source.name.as_deref() == Some(file_name.as_str())) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("File {} sent twice", file_name) }))
+                ).into_response());
             }
-            
+
+            let bytes = field.bytes().await.map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("Parsing to source file: {}", e) }))
+                ).into_response()
+            })?;
+
+            let content = String::from_utf8(bytes.to_vec()).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": format!("Parsing to UTF-8 source file: {}", e) }))
+                ).into_response()
+            })?;
+
+            sources.push(SourceFile { name: Some(file_name), content });
         }
 
     }
 
-    for required in ["user_id", "lang", "source", "problem_id"] {
+    for required in ["lang", "source", "problem_id"] {
         if !field_check.contains(required) {
             return Err((
                 StatusCode::BAD_REQUEST,
@@ -183,7 +151,6 @@ pub async fn upload(Extension(pool): Extension<PgPool>, mut multipart: Multipart
         }
     }
 
-    // Compilar y ejecutar
     let user_id_query = sqlx::query("SELECT EXISTS (SELECT 1 FROM USERS WHERE USER_ID = $1)")
         .bind(user_id)
         .fetch_one(&pool)
@@ -204,251 +171,263 @@ pub async fn upload(Extension(pool): Extension<PgPool>, mut multipart: Multipart
         ).into_response());
     }
 
-    let problem_query = "
-        SELECT PROBLEM_TEST_CASES_URL, PROBLEM_OUTPUTS_URL, PROBLEM_MEMORY_MB_LIMIT, PROBLEM_TIME_MS_LIMIT 
-        FROM PROBLEMS
-        WHERE PROBLEM_ID = $1;        
-    ";
-    
-    let problem_query_result = sqlx::query(problem_query)
+    let grader_url: Option<String> = sqlx::query("SELECT PROBLEM_GRADER_URL FROM PROBLEMS WHERE PROBLEM_ID = $1")
         .bind(problem_id)
-        .fetch_one(&pool)
-        .await;
-    
-    let row = problem_query_result.map_err(|_| {
-        (
-            StatusCode::NOT_FOUND,
-            Json(json!({ "error": "Problem not found!" }))
-        ).into_response()
-    })?;
-    
-    let test_cases_url: String = row.get("problem_test_cases_url");
-    let outputs_url: String = row.get("problem_outputs_url");
-    let time_limit: i32 = row.get("problem_time_ms_limit");
-    let memory_limit: i32 = row.get("problem_memory_mb_limit");
-    
-
-    let version =  avaliable_langs.get(&lang).cloned().ok_or_else(|| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Language version not found!" }))
-        ).into_response()
-    })?;
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("DB error: {}", e) }))
+            ).into_response()
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Problem not found!" }))
+            ).into_response()
+        })?
+        .get("problem_grader_url");
 
-    let source = CompileStruct {
-        lang: lang.clone(),
-        version: version.clone(),
-        source_code: source_code.clone(),
-        memory_limit: memory_limit,
-        time_limit: time_limit,
-    };
+    let language = get_language(&pool, &lang)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("DB error: {}", e) }))
+            ).into_response()
+        })?
+        .filter(|language| language.enabled)
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "language not valid" }))
+            ).into_response()
+        })?;
 
-    let compile_result:Result<String,String> = compile(&judge_url, source, &test_cases_url, &outputs_url).await;
-    
-    let veredict = compile_result.map_err( |e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": format!("Cannot compile the source file! Error: {}", e) }))
-        ).into_response()
-    })?;
+    // Los archivos del grader se compilan junto al envío, así que sus nombres no se pueden reutilizar
+    if let Some(grader_url) = &grader_url {
+        let grader = load_grader(grader_url, &language.name).await.map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": e }))
+            ).into_response()
+        })?;
 
-    // Guardar código fuente
-    let mut saved_file = File::create(&upload_path).await.map_err(|_| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Error writing the source code file!" }))
-        ).into_response()
-    })?;
+        if let Some(taken) = sources.iter().find(|source| grader.iter().any(|file| file.name == source.name)) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("File {} is provided by the problem", taken.name.as_deref().unwrap_or_default()) }))
+            ).into_response());
+        }
+    }
 
-    saved_file.write(source_code.as_bytes()).await.map_err(|_|{
+    let db_error = |e: sqlx::Error| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "Error writing the source code file!" }))
+            Json(json!({ "error": format!("Database error: {}", e) }))
         ).into_response()
-    })?;
+    };
 
-    println!("File {} saved!", upload_path);
-    // Guardar en la DB
+    // Una copia exacta de un envío anterior recibe el mismo veredicto sin volver a juzgarse
+    let mut hasher = Sha256::new();
+    for source in &sources {
+        hasher.update(source.name.as_deref().unwrap_or_default().as_bytes());
+        hasher.update([0]);
+        hasher.update(source.content.as_bytes());
+        hasher.update([0]);
+    }
+    let source_hash = format!("{:x}", hasher.finalize());
 
-    let save_query = "
-        INSERT INTO SUBMISSIONS(
-            USER_ID,
-            PROBLEM_ID,
-            SUBMISSION_URL,
-            SUBMISSION_ANSWER_CODE
-        )
-        VALUES ($1, $2, $3, $4)
-        RETURNING
+    let duplicate_query = "
+        SELECT
             SUBMISSION_ID,
             USER_ID,
             PROBLEM_ID,
+            SUBMISSION_LANGUAGE,
             SUBMISSION_URL,
-            SUBMISSION_ANSWER_CODE
+            SUBMISSION_ANSWER_CODE,
+            SUBMISSION_EARNED_SCORE,
+            SUBMISSION_CREATED_AT
+        FROM SUBMISSIONS
+        WHERE USER_ID = $1
+        AND PROBLEM_ID = $2
+        AND SUBMISSION_LANGUAGE = $3
+        AND SUBMISSION_SOURCE_HASH = $4
+        AND SUBMISSION_ANSWER_CODE <> 'IE'
+        ORDER BY SUBMISSION_ID DESC
+        LIMIT 1
     ";
 
-    let result_save_submission = sqlx::query(save_query)
+    let duplicate = sqlx::query(duplicate_query)
         .bind(user_id)
         .bind(problem_id)
-        .bind(upload_path.clone())
-        .bind(veredict)
-        .fetch_one(&pool)
-        .await;
-
-
-    match result_save_submission {
-        Ok(row) => {
-            let body = json!({
-                "status": true,
-                "submission_id":row.get::<i32, _>("submission_id"),
-                "user_id": row.get::<i32, _>("user_id"),
-                "problem_id": row.get::<i32, _>("problem_id"),
-                "upload_path" : row.get::<String,_>("submission_url"),
-                "veredict": row.get::<String, _>("submission_answer_code"),
-                "lang": lang,
-            });
-
-            Ok(Json(body))
-        }
-
-        Err(e) => {
-            let error_body = json!({
-                "error": format!("Database error: {}", e)
-            });
-
-            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(error_body)).into_response())
-        }
-        }
-
-}
-
-async fn compile(judge_url: &str, source: CompileStruct ,inputs_url: &str, outputs_url : &str) -> Result<String, String> {
-    let client = Client::new();
-    let inputs = match load_cases(&inputs_url).await {
-        Ok(mapa) => mapa,
-        Err(e) => return Err(format!("Error loading inputs: {}", e)),
-    };
-    let outputs = match load_cases(&outputs_url).await {
-        Ok(mapa) => mapa,
-        Err(e) => return Err(format!("Error loading outputs: {}", e)),
-    };
-
-    let mut request = CompileRequest {
-        language: source.lang.clone(),
-        version: source.version.clone(),
-        files: vec![SourceFile {
-            content: source.source_code.clone(),
-        }],
-        stdin: String::new(), 
-    };
-    println!("{}", source.source_code);
-    print!("{}", judge_url);
-    for (file, input) in &inputs {
-        if let Some(expected_stdout) = outputs.get(file) {
-            println!("Caso: {}", file);
-            println!("Input: {}", input);
-            println!("Expected: {}", expected_stdout);
-            
-            request.stdin = input.clone();
-
-            let res = client
-                .post(judge_url)
-                .json(&request)
-                .send()
-                .await
-                .map_err(|e| format!("error sending sorce code to compile {e}"))?;
-            
-           
-            //let result1 = res1.text().await.map_err(|e| format!("Error reading response text: {e}"))?;
-            //let result: CompileResponse = res.json().await.map_err(|e| format!("Invalid response from judge {e}"))?;
-            let text = res.text().await.map_err(|e| format!("No se pudo leer el body: {e}"))?;
-            println!("Respuesta cruda del juez:\n{text}");
-
-            // Ahora convertís el texto en JSON
-            let result: CompileResponse = serde_json::from_str(&text)
-                .map_err(|e| format!("Respuesta inválida del juez: {e}"))?;
-            
-            println!("{:?}", result);
-
-
-            let result_case = get_verdict(&result, &expected_stdout,source.time_limit,source.memory_limit);
-            if result_case != "AC".to_string() {
-                return Ok(result_case)
-            }
+        .bind(&language.name)
+        .bind(&source_hash)
+        .fetch_optional(&pool)
+        .await
+        .map_err(db_error)?;
 
-        } else {
-            println!("Archivo {} no tiene salida esperada", file);
-            return Err(format!("File {} doesn't have expected output", file));
-        }
+    if let Some(row) = duplicate {
+        let mut body = submission_body(&row);
+        body["duplicate"] = json!(true);
+        body["score"] = json!(row.get::<i32, _>("submission_earned_score"));
+        return Ok((StatusCode::OK, Json(body)).into_response());
     }
 
+    if let RateLimit::Limited { retry_after_secs } = take_submission_token(&pool, &limits, user_id, problem_id)
+        .await
+        .map_err(db_error)?
+    {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Json(json!({
+                "error": format!("Too many submissions, try again in {} seconds", retry_after_secs),
+                "retry_after": retry_after_secs,
+            }))
+        ).into_response());
+    }
 
+    let upload_id = Uuid::new_v4();
+    let upload_path = format!("{}/{}.{}", save_path, upload_id, language.extension);
 
-    Ok("AC".to_string())
-}
+    if !Path::new(&save_path).exists(){
+        tokio::fs::create_dir(&save_path).await.map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Creating Server: {}", e) }))
+            ).into_response()
+        })?;
+    }
 
+    let files_dir = format!("{}/{}", save_path, upload_id);
 
-async fn load_cases(dir: &str) -> Result<HashMap<String, String>, String> {
-    let mut files = HashMap::new();
-    let mut entries = fs::read_dir(dir)
-        .await
-        .map_err(|_| format!("Error reading {}", dir))?;
-
-    while let Some(entry) = entries.next_entry().await.map_err(|_| "Error reading the file")? {
-        let path = entry.path();
-        if path.is_file() {
-            if let Some(file_name) = path.file_stem().and_then(|f| f.to_str()) {
-                let contenido = fs::read_to_string(&path)
-                    .await
-                    .map_err(|_| format!("Error reading the file {}", file_name))?;
-                files.insert(file_name.to_string(), contenido);
+    let saved: Result<PgRow, Response> = async {
+        // Guardar código fuente: el primer archivo como siempre y el resto en una carpeta propia
+        let mut file_paths = vec![upload_path.clone()];
+        if sources.len() > 1 {
+            tokio::fs::create_dir(&files_dir).await.map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Error writing the source code file!" }))
+                ).into_response()
+            })?;
+            for source in &sources[1..] {
+                file_paths.push(format!("{}/{}", files_dir, source.name.as_deref().unwrap_or_default()));
             }
         }
-    }
 
-    Ok(files)
-}
+        for (path, source) in file_paths.iter().zip(&sources) {
+            let mut saved_file = File::create(path).await.map_err(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Error writing the source code file!" }))
+                ).into_response()
+            })?;
 
+            saved_file.write_all(source.content.as_bytes()).await.map_err(|_|{
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": "Error writing the source code file!" }))
+                ).into_response()
+            })?;
+        }
 
-fn get_verdict(response: &CompileResponse, expected_output: &str, time_limit: i32, memory_limit: i32) -> String {
-    if let Some(compile) = &response.compile{
-        if let Some(run_code) = compile.code   {
-            if run_code != 0{
-                return "CE".into(); 
-            }
+        println!("File {} saved!", upload_path);
+        // Guardar en la DB y encolar el juzgamiento
+
+        let save_query = "
+            INSERT INTO SUBMISSIONS(
+                USER_ID,
+                PROBLEM_ID,
+                SUBMISSION_LANGUAGE,
+                SUBMISSION_URL,
+                SUBMISSION_FILE_NAME,
+                SUBMISSION_SOURCE_HASH,
+                SUBMISSION_ANSWER_CODE
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, 'PENDING')
+            RETURNING
+                SUBMISSION_ID,
+                USER_ID,
+                PROBLEM_ID,
+                SUBMISSION_LANGUAGE,
+                SUBMISSION_URL,
+                SUBMISSION_ANSWER_CODE,
+                SUBMISSION_CREATED_AT
+        ";
+
+        let mut tx = pool.begin().await.map_err(db_error)?;
+
+        let row = sqlx::query(save_query)
+            .bind(user_id)
+            .bind(problem_id)
+            .bind(&language.name)
+            .bind(upload_path.clone())
+            .bind(&sources[0].name)
+            .bind(&source_hash)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        let submission_id: i32 = row.get("submission_id");
+
+        for (order, (path, source)) in file_paths.iter().zip(&sources).enumerate().skip(1) {
+            sqlx::query("INSERT INTO SUBMISSION_FILES(SUBMISSION_ID, FILE_ORDER, FILE_NAME, FILE_URL) VALUES ($1, $2, $3, $4)")
+                .bind(submission_id)
+                .bind(order as i32)
+                .bind(&source.name)
+                .bind(path)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
         }
-    }
 
+        queue::enqueue(&mut tx, submission_id, &language.name, &language.version)
+            .await
+            .map_err(db_error)?;
 
-    let run = &response.run;
-
-    if let Some(signal) = &run.signal {
-        if signal == "SIGKILL" {
-            if let (Some(wall_time), Some(memory)) = (run.wall_time, run.memory) {
-                if wall_time >= time_limit - 5 {
-                    return "TLE".into(); 
-                } else if memory >= memory_limit - 5000 {
-                    return "MLE".into(); 
-                } else {
-                    return "RTE".into(); 
-                }
-            } else {
-                return "RTE".into();
-            }
-        } else {
-            return "RTE".into(); 
-        }
+        tx.commit().await.map_err(db_error)?;
+
+        Ok(row)
     }
-    if let Some(code) = run.code {
-        if code != 0 {
-            return "RTE".into();
+    .await;
+
+    // Si algo falla después de escribir, no dejar archivos que ningún envío referencie
+    let row = match saved {
+        Ok(row) => row,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&upload_path).await;
+            let _ = tokio::fs::remove_dir_all(&files_dir).await;
+            return Err(e);
         }
-    } else {
-        return "RTE".into();
-    }
+    };
 
-    if run.stdout.trim() == expected_output.trim() {
-        "AC".into()
-    } else {
-        "WA".into()
-    }
-}
\ No newline at end of file
+    let submission_id: i32 = row.get("submission_id");
+    status.queued(submission_id, user_id);
+    judge_queue.wake_worker();
+
+    Ok((StatusCode::ACCEPTED, Json(submission_body(&row))).into_response())
+}
+
+// Solo nombres simples, que se guardan tal cual en disco y en el juez.
+fn valid_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 100
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn submission_body(row: &PgRow) -> Value {
+    json!({
+        "status": true,
+        "submission_id": row.get::<i32, _>("submission_id"),
+        "user_id": row.get::<i32, _>("user_id"),
+        "problem_id": row.get::<i32, _>("problem_id"),
+        "upload_path" : row.get::<String,_>("submission_url"),
+        "veredict": row.get::<String, _>("submission_answer_code"),
+        "lang": row.get::<String, _>("submission_language"),
+        "created_at": row.get::<DateTime<Utc>, _>("submission_created_at"),
+    })
+}
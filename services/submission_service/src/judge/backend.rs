@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::judge::client::JudgeClientConfig;
+use crate::judge::local::LocalBackend;
+use crate::judge::piston::PistonBackend;
+use crate::models::compile_models::{CompileStruct, ResultObject, SourceFile};
+use crate::models::language_models::Runtime;
+
+/// A compiled submission, ready to be run against every test case.
+pub struct Artifact {
+    pub source: CompileStruct,
+    pub workdir: Option<PathBuf>,
+}
+
+pub struct InteractiveRun {
+    pub contestant: ResultObject,
+    pub interactor: ResultObject,
+}
+
+pub enum Compilation {
+    Ready(Artifact),
+    Failed(ResultObject),
+}
+
+#[async_trait]
+pub trait JudgeBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Builds the submission once for all its test cases. Backends that can't
+    /// keep a build between runs return the sources as the artifact and
+    /// report a compile error from the first run instead.
+    async fn compile(&self, source: &CompileStruct) -> Result<Compilation, String>;
+
+    async fn run(&self, artifact: &Artifact, stdin: &str) -> Result<ResultObject, String> {
+        self.run_with_files(artifact, stdin, &[], &[]).await
+    }
+
+    /// Runs the artifact with extra named files next to it and command line
+    /// arguments, which is how testlib-style checkers receive their input.
+    async fn run_with_files(&self, artifact: &Artifact, stdin: &str, args: &[String], files: &[SourceFile]) -> Result<ResultObject, String>;
+
+    /// Runs the contestant connected to the interactor through pipes. The
+    /// interactor gets `input.txt`, `output.txt` and `answer.txt` as arguments.
+    async fn run_interactive(&self, _artifact: &Artifact, _interactor: &Artifact, _input: &str, _answer: &str) -> Result<InteractiveRun, String> {
+        Err(format!("The {} judge backend does not support interactive problems", self.name()))
+    }
+
+    async fn release(&self, _artifact: Artifact) {}
+
+    async fn runtimes(&self) -> Result<Vec<Runtime>, String> {
+        Err(format!("The {} judge backend cannot list its runtimes", self.name()))
+    }
+
+    /// False while the judge is known to be down, so no work is sent to it.
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// Builds the backend selected by `JUDGE_BACKEND` (`piston` by default, or `local`).
+pub fn backend_from_env() -> Arc<dyn JudgeBackend> {
+    let kind = std::env::var("JUDGE_BACKEND").unwrap_or_else(|_| "piston".to_string());
+
+    match kind.as_str() {
+        "piston" => {
+            let judge_url = std::env::var("JUDGE_IP").expect("JUDGE_IP must be set");
+            Arc::new(PistonBackend::new(judge_url, JudgeClientConfig::from_env()))
+        }
+        "local" => Arc::new(LocalBackend::from_env()),
+        other => panic!("Unknown JUDGE_BACKEND: {}", other),
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+use tokio::fs::read_to_string;
+use crate::judge::backend::{Artifact, Compilation, JudgeBackend};
+use crate::models::compile_models::{CompileStruct, SourceFile};
+
+pub const TESTLIB_LANGUAGE: &str = "cpp";
+const CHECKER_TIME_MS: i32 = 10_000;
+const CHECKER_MEMORY_MB: i32 = 512;
+
+pub struct CheckerConfig {
+    pub mode: String,
+    /// Tolerance of the `float` mode, absolute and relative at once.
+    pub epsilon: f64,
+    pub checker_url: Option<String>,
+    pub testlib_version: Option<String>,
+}
+
+pub enum Checker {
+    Exact,
+    Tokens,
+    Float(f64),
+    CaseInsensitive,
+    Custom(Artifact),
+}
+
+impl Checker {
+    pub async fn build(backend: &dyn JudgeBackend, config: &CheckerConfig) -> Result<Checker, String> {
+        let checker = match config.mode.as_str() {
+            "exact" => Checker::Exact,
+            "tokens" => Checker::Tokens,
+            "float" => Checker::Float(config.epsilon),
+            "case_insensitive" => Checker::CaseInsensitive,
+            "custom" => {
+                let checker_url = config.checker_url.as_deref().ok_or("Custom checker without checker file")?;
+                Checker::Custom(compile_testlib_program(backend, checker_url, config.testlib_version.as_deref()).await?)
+            }
+            other => return Err(format!("Unknown checker mode {}", other)),
+        };
+
+        Ok(checker)
+    }
+
+    /// `Ok(true)` means accepted. Errors are reserved for a broken custom
+    /// checker, which is a problem setup issue and not the contestant's fault.
+    pub async fn check(&self, backend: &dyn JudgeBackend, input: &str, output: &str, expected: &str) -> Result<bool, String> {
+        let accepted = match self {
+            Checker::Exact => output.trim() == expected.trim(),
+            Checker::Tokens => output.split_whitespace().eq(expected.split_whitespace()),
+            Checker::CaseInsensitive => output
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .eq(expected.split_whitespace().map(str::to_lowercase)),
+            Checker::Float(epsilon) => {
+                let output_tokens: Vec<&str> = output.split_whitespace().collect();
+                let expected_tokens: Vec<&str> = expected.split_whitespace().collect();
+                output_tokens.len() == expected_tokens.len()
+                    && output_tokens
+                        .iter()
+                        .zip(&expected_tokens)
+                        .all(|(a, b)| float_token_matches(a, b, *epsilon))
+            }
+            Checker::Custom(artifact) => return run_custom(backend, artifact, input, output, expected).await,
+        };
+
+        Ok(accepted)
+    }
+
+    pub async fn release(self, backend: &dyn JudgeBackend) {
+        if let Checker::Custom(artifact) = self {
+            backend.release(artifact).await;
+        }
+    }
+}
+
+/// The usual "absolute or relative error" rule: a number is accepted when it
+/// is within `epsilon` of the expected one, or within `epsilon` times its
+/// magnitude. A single value is both tolerances, so 1e-6 accepts 1000.0009
+/// for 1000 and 0.0000009 for 0. Tokens that are not numbers must match exactly.
+fn float_token_matches(output: &str, expected: &str, epsilon: f64) -> bool {
+    match (output.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(a), Ok(b)) => {
+            if a.is_nan() || b.is_nan() {
+                return a.is_nan() && b.is_nan();
+            }
+            let diff = (a - b).abs();
+            diff <= epsilon || diff <= epsilon * b.abs()
+        }
+        _ => output == expected,
+    }
+}
+
+/// Compiles a problem-supplied C++ program (checker or interactor), together
+/// with the `testlib.h` stored next to it when there is one. `version` is the
+/// registered C++ version.
+pub async fn compile_testlib_program(backend: &dyn JudgeBackend, program_url: &str, version: Option<&str>) -> Result<Artifact, String> {
+    let version = version.ok_or("C++ is not in the language registry")?;
+    let source_code = read_to_string(program_url)
+        .await
+        .map_err(|e| format!("Cannot read {}: {}", program_url, e))?;
+
+    let mut files = vec![SourceFile { name: None, content: source_code }];
+    if let Some(dir) = Path::new(program_url).parent()
+        && let Ok(testlib) = read_to_string(dir.join("testlib.h")).await
+    {
+        files.push(SourceFile {
+            name: Some("testlib.h".to_string()),
+            content: testlib,
+        });
+    }
+
+    let source = CompileStruct {
+        lang: TESTLIB_LANGUAGE.to_string(),
+        version: version.to_string(),
+        files,
+        memory_limit: CHECKER_MEMORY_MB,
+        time_limit: CHECKER_TIME_MS,
+    };
+
+    match backend.compile(&source).await? {
+        Compilation::Ready(artifact) => Ok(artifact),
+        Compilation::Failed(output) => Err(format!("{} does not compile: {}", program_url, output.stderr)),
+    }
+}
+
+// testlib exit codes: 0 accepted, 1 wrong answer, 2 presentation error.
+async fn run_custom(backend: &dyn JudgeBackend, artifact: &Artifact, input: &str, output: &str, expected: &str) -> Result<bool, String> {
+    let file = |name: &str, content: &str| SourceFile {
+        name: Some(name.to_string()),
+        content: content.to_string(),
+    };
+
+    let files = [
+        file("input.txt", input),
+        file("output.txt", output),
+        file("answer.txt", expected),
+    ];
+    let args = ["input.txt", "output.txt", "answer.txt"].map(String::from);
+
+    let result = backend.run_with_files(artifact, "", &args, &files).await?;
+
+    // The compiler's exit code would otherwise read as a verdict.
+    if result.status.as_deref() == Some("CE") {
+        return Err(format!("Checker does not compile: {}", result.stderr));
+    }
+
+    match result.code {
+        Some(0) => Ok(true),
+        Some(1) | Some(2) => Ok(false),
+        _ => Err(format!(
+            "Checker failed (code {:?}, signal {:?}): {}",
+            result.code, result.signal, result.stderr
+        )),
+    }
+}
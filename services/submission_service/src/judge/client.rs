@@ -0,0 +1,202 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use crate::judge::worker::env_or;
+
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+const UNAVAILABLE: &str = "Judge unavailable";
+
+/// Whether a judge error means the judge could not be reached at all, rather
+/// than a problem with the submission or the problem package.
+pub fn is_unavailable(error: &str) -> bool {
+    error.contains(UNAVAILABLE)
+}
+
+#[derive(Clone)]
+pub struct JudgeClientConfig {
+    pub connect_timeout: Duration,
+    pub request_timeout: Duration,
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+    pub breaker_threshold: u32,
+    pub breaker_cooldown: Duration,
+}
+
+impl JudgeClientConfig {
+    pub fn from_env() -> Self {
+        JudgeClientConfig {
+            connect_timeout: Duration::from_millis(env_or("JUDGE_CONNECT_TIMEOUT_MS", 2000)),
+            request_timeout: Duration::from_millis(env_or("JUDGE_REQUEST_TIMEOUT_MS", 30000)),
+            max_retries: env_or("JUDGE_MAX_RETRIES", 2),
+            retry_backoff: Duration::from_millis(env_or("JUDGE_RETRY_BACKOFF_MS", 250)),
+            breaker_threshold: env_or("JUDGE_BREAKER_THRESHOLD", 5),
+            breaker_cooldown: Duration::from_secs(env_or("JUDGE_BREAKER_COOLDOWN_SECS", 30)),
+        }
+    }
+}
+
+/// Consecutive failed calls. Once `threshold` is reached the breaker opens and
+/// calls fail fast until the cooldown has passed. Then a single call goes
+/// through as the probe, and the rest keep failing fast until it is answered.
+#[derive(Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+impl Breaker {
+    fn is_closed(&self) -> bool {
+        self.open_until.is_none()
+    }
+
+    fn is_half_open(&self) -> bool {
+        self.open_until.is_some_and(|until| Instant::now() >= until) && !self.probing
+    }
+}
+
+/// Clears the probe flag if the probe is dropped before it is answered.
+struct Probe<'a> {
+    breaker: Option<&'a Mutex<Breaker>>,
+}
+
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        if let Some(breaker) = self.breaker {
+            breaker.lock().unwrap().probing = false;
+        }
+    }
+}
+
+enum Failure {
+    /// The judge could not be reached or is overloaded; worth another try.
+    Transient(String),
+    /// The judge answered but rejected the request.
+    Rejected(String),
+}
+
+/// HTTP client shared by every call to the judge, with one connection pool.
+/// Only use it for idempotent requests: failed calls are sent again.
+pub struct JudgeClient {
+    client: Client,
+    config: JudgeClientConfig,
+    breaker: Mutex<Breaker>,
+}
+
+impl JudgeClient {
+    pub fn new(config: JudgeClientConfig) -> Self {
+        let client = Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .build()
+            .expect("Failed to build the judge HTTP client");
+
+        JudgeClient {
+            client,
+            config,
+            breaker: Mutex::new(Breaker::default()),
+        }
+    }
+
+    /// False while the breaker is open or its probe is still in flight.
+    pub fn is_available(&self) -> bool {
+        let breaker = self.breaker.lock().unwrap();
+        breaker.is_closed() || breaker.is_half_open()
+    }
+
+    /// Lets the call through, as the probe when the breaker is half open.
+    fn admit(&self) -> Option<Probe<'_>> {
+        let mut breaker = self.breaker.lock().unwrap();
+
+        if breaker.is_closed() {
+            return Some(Probe { breaker: None });
+        }
+
+        if breaker.is_half_open() {
+            breaker.probing = true;
+            return Some(Probe { breaker: Some(&self.breaker) });
+        }
+
+        None
+    }
+
+    /// `extra_timeout` is added to the request timeout, for runs whose own
+    /// time limit is part of the response time.
+    pub async fn send_json<R: DeserializeOwned>(&self, request: impl Fn(&Client) -> RequestBuilder, extra_timeout: Duration) -> Result<R, String> {
+        let Some(_probe) = self.admit() else {
+            return Err(format!("{}: too many failed requests, waiting before trying again", UNAVAILABLE));
+        };
+
+        let timeout = self.config.request_timeout + extra_timeout;
+        let mut backoff = self.config.retry_backoff;
+        let mut attempt = 0;
+
+        loop {
+            match self.send_once(request(&self.client).timeout(timeout)).await {
+                Ok(response) => {
+                    self.record_success();
+                    return Ok(response);
+                }
+                Err(Failure::Rejected(e)) => {
+                    self.record_success();
+                    return Err(e);
+                }
+                Err(Failure::Transient(e)) if attempt < self.config.max_retries => {
+                    attempt += 1;
+                    eprintln!("Judge request failed, retry {} of {} in {:?}: {}", attempt, self.config.max_retries, backoff, e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                }
+                Err(Failure::Transient(e)) => {
+                    self.record_failure();
+                    return Err(format!("{}: {}", UNAVAILABLE, e));
+                }
+            }
+        }
+    }
+
+    async fn send_once<R: DeserializeOwned>(&self, request: RequestBuilder) -> Result<R, Failure> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| Failure::Transient(format!("cannot reach the judge: {}", e)))?;
+
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Failure::Transient(format!("cannot read the judge response: {}", e)))?;
+
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(Failure::Transient(format!("judge answered {}: {}", status, body)));
+        }
+
+        if !status.is_success() {
+            return Err(Failure::Rejected(format!("Judge rejected the request with {}: {}", status, body)));
+        }
+
+        serde_json::from_str(&body).map_err(|e| Failure::Rejected(format!("Invalid judge response: {}", e)))
+    }
+
+    fn record_success(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        if breaker.open_until.is_some() {
+            println!("Judge is reachable again, closing the circuit breaker");
+        }
+        *breaker = Breaker::default();
+    }
+
+    fn record_failure(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.failures += 1;
+
+        if breaker.failures >= self.config.breaker_threshold {
+            eprintln!(
+                "Judge failed {} requests in a row, pausing requests for {:?}",
+                breaker.failures, self.config.breaker_cooldown
+            );
+            breaker.open_until = Some(Instant::now() + self.config.breaker_cooldown);
+        }
+    }
+}
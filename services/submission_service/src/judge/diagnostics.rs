@@ -0,0 +1,82 @@
+pub const COMPILE_OUTPUT_LIMIT_BYTES: usize = 8 * 1024;
+pub const STDERR_LIMIT_BYTES: usize = 4 * 1024;
+
+/// Compiler or runtime output as students get to see it: without the judge's
+/// sandbox directories, terminal escapes or control characters, and cut to
+/// `limit` bytes.
+pub fn sanitize_output(output: &str, limit: usize) -> String {
+    let without_paths = strip_sandbox_dirs(output);
+
+    let mut clean = String::with_capacity(without_paths.len());
+    let mut chars = without_paths.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // ANSI escape sequence, e.g. the colors of gcc diagnostics.
+            '\u{1b}' => {
+                if chars.next_if_eq(&'[').is_some() {
+                    for next in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&next) {
+                            break;
+                        }
+                    }
+                }
+            }
+            '\n' | '\t' => clean.push(c),
+            c if c.is_control() => {}
+            c => clean.push(c),
+        }
+    }
+
+    truncate(clean, limit)
+}
+
+pub fn truncate(mut output: String, limit: usize) -> String {
+    if output.len() > limit {
+        let mut end = limit;
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.truncate(end);
+        output.push_str("\n[output truncated]");
+    }
+    output
+}
+
+// Every sandbox runs in a directory named with a UUID (`/tmp/dou-judge/<uuid>/`,
+// `/piston/jobs/<uuid>/`), so a path up to such a directory is dropped and
+// only the file name the student knows is left.
+fn strip_sandbox_dirs(output: &str) -> String {
+    let mut result = String::with_capacity(output.len());
+    let mut rest = output;
+
+    while let Some((start, end)) = find_sandbox_dir(rest) {
+        result.push_str(&rest[..start]);
+        rest = &rest[end..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+fn find_sandbox_dir(text: &str) -> Option<(usize, usize)> {
+    let bytes = text.as_bytes();
+
+    for index in 0..bytes.len() {
+        if bytes[index] == b'/' && is_uuid(&bytes[index + 1..]) && bytes.get(index + 37) == Some(&b'/') {
+            let start = text[..index]
+                .rfind(|c: char| c.is_whitespace() || matches!(c, '\'' | '"' | '(' | '`'))
+                .map_or(0, |position| position + 1);
+            return Some((start, index + 38));
+        }
+    }
+
+    None
+}
+
+fn is_uuid(bytes: &[u8]) -> bool {
+    bytes.len() >= 36
+        && bytes[..36].iter().enumerate().all(|(index, byte)| match index {
+            8 | 13 | 18 | 23 => *byte == b'-',
+            _ => byte.is_ascii_hexdigit(),
+        })
+}
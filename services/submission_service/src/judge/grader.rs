@@ -0,0 +1,41 @@
+use std::path::Path;
+use tokio::fs;
+use crate::models::compile_models::SourceFile;
+
+/// The files a problem provides for one language, from `<grader_url>/<lang>/`,
+/// sorted by name.
+pub async fn load_grader(grader_url: &str, lang: &str) -> Result<Vec<SourceFile>, String> {
+    let dir = Path::new(grader_url).join(lang);
+    let mut entries = fs::read_dir(&dir)
+        .await
+        .map_err(|_| format!("The problem has no grader for {}", lang))?;
+
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(|_| format!("Error reading {}", dir.display()))? {
+        let path = entry.path();
+        if path.is_file()
+            && let Some(name) = path.file_name().and_then(|f| f.to_str())
+        {
+            let content = fs::read_to_string(&path)
+                .await
+                .map_err(|_| format!("Error reading the grader file {}", name))?;
+            files.push(SourceFile { name: Some(name.to_string()), content });
+        }
+    }
+
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// The grader's source files go first, so a grader with the program's entry
+/// point is what runs, then the submission and then the grader's headers.
+pub fn with_grader(grader: Vec<SourceFile>, submission: Vec<SourceFile>, extension: &str) -> Vec<SourceFile> {
+    let (sources, others): (Vec<SourceFile>, Vec<SourceFile>) = grader.into_iter().partition(|file| {
+        file.name
+            .as_deref()
+            .and_then(|name| Path::new(name).extension())
+            .is_some_and(|e| e == extension)
+    });
+
+    sources.into_iter().chain(submission).chain(others).collect()
+}
@@ -0,0 +1,97 @@
+use std::cmp::Ordering;
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use crate::judge::backend::JudgeBackend;
+use crate::models::language_models::{Language, Runtime};
+
+const LANGUAGE_COLUMNS: &str = "
+    LANGUAGE_NAME,
+    LANGUAGE_DISPLAY_NAME,
+    LANGUAGE_VERSION,
+    LANGUAGE_EXTENSION,
+    LANGUAGE_TIME_MULTIPLIER,
+    LANGUAGE_MEMORY_OVERHEAD_MB,
+    LANGUAGE_ENABLED
+";
+
+pub async fn get_language(pool: &PgPool, name: &str) -> Result<Option<Language>, sqlx::Error> {
+    let query = format!("SELECT {} FROM LANGUAGES WHERE LANGUAGE_NAME = $1", LANGUAGE_COLUMNS);
+
+    let row = sqlx::query(&query).bind(name).fetch_optional(pool).await?;
+
+    Ok(row.map(|row| to_language(&row)))
+}
+
+pub async fn list_languages(pool: &PgPool, include_disabled: bool) -> Result<Vec<Language>, sqlx::Error> {
+    let query = format!(
+        "SELECT {} FROM LANGUAGES WHERE LANGUAGE_ENABLED OR $1 ORDER BY LANGUAGE_NAME ASC",
+        LANGUAGE_COLUMNS
+    );
+
+    let rows = sqlx::query(&query).bind(include_disabled).fetch_all(pool).await?;
+
+    Ok(rows.iter().map(to_language).collect())
+}
+
+/// Points every registered language at the newest version the judge offers,
+/// and disables the ones it cannot run at all.
+pub async fn discover_languages(pool: &PgPool, backend: &dyn JudgeBackend) -> Result<(), String> {
+    let runtimes = backend.runtimes().await?;
+    let languages = list_languages(pool, true)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+
+    for language in languages {
+        let newest = runtimes
+            .iter()
+            .filter(|runtime| runtime_matches(runtime, &language.name))
+            .max_by(|a, b| compare_versions(&a.version, &b.version));
+
+        let (version, enabled) = match newest {
+            Some(runtime) => (runtime.version.clone(), language.enabled),
+            None => {
+                println!("Language {} is not available on the {} judge, disabling it", language.name, backend.name());
+                (language.version.clone(), false)
+            }
+        };
+
+        if version == language.version && enabled == language.enabled {
+            continue;
+        }
+
+        sqlx::query("UPDATE LANGUAGES SET LANGUAGE_VERSION = $1, LANGUAGE_ENABLED = $2 WHERE LANGUAGE_NAME = $3")
+            .bind(&version)
+            .bind(enabled)
+            .bind(&language.name)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("DB error: {}", e))?;
+
+        println!("Language {} now runs version {}", language.name, version);
+    }
+
+    Ok(())
+}
+
+fn to_language(row: &PgRow) -> Language {
+    Language {
+        name: row.get("language_name"),
+        display_name: row.get("language_display_name"),
+        version: row.get("language_version"),
+        extension: row.get("language_extension"),
+        time_multiplier: row.get("language_time_multiplier"),
+        memory_overhead_mb: row.get("language_memory_overhead_mb"),
+        enabled: row.get("language_enabled"),
+    }
+}
+
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+    };
+    parts(a).cmp(&parts(b))
+}
+
+fn runtime_matches(runtime: &Runtime, name: &str) -> bool {
+    runtime.language == name || runtime.aliases.iter().any(|alias| alias == name)
+}
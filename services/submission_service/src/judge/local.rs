@@ -0,0 +1,381 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use async_trait::async_trait;
+use tokio::fs;
+use uuid::Uuid;
+use crate::judge::backend::{Artifact, Compilation, InteractiveRun, JudgeBackend};
+use crate::judge::sandbox::{run_interactive_sandboxed, run_sandboxed, SandboxLimits, SandboxResult, SandboxUser};
+use crate::models::compile_models::{CompileStruct, ResultObject, SourceFile};
+
+const COMPILE_TIME_MS: u64 = 10_000;
+const OUTPUT_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+// Address space on top of the memory limit for the runtime's own mappings.
+// Running out of it fails an allocation, which get_verdict reports as MLE.
+const MEMORY_OVERHEAD_MB: u64 = 64;
+
+pub struct LocalBackend {
+    workdir: PathBuf,
+    namespaces: bool,
+    max_processes: u64,
+    users: Option<UserPool>,
+}
+
+/// Accounts handed out round-robin to the sandboxes when the judge runs as
+/// root, so concurrent runs do not share files or the process limit.
+struct UserPool {
+    base: u32,
+    count: u32,
+    next: AtomicU32,
+}
+
+impl UserPool {
+    fn next(&self) -> SandboxUser {
+        let uid = self.base + self.next.fetch_add(1, Ordering::Relaxed) % self.count;
+        SandboxUser { uid, gid: uid }
+    }
+}
+
+struct LocalLanguage {
+    file_name: &'static str,
+    source_extension: &'static str,
+    limit_address_space: bool,
+}
+
+fn local_language(lang: &str) -> Option<LocalLanguage> {
+    let language = match lang {
+        "cpp" => LocalLanguage { file_name: "main.cpp", source_extension: "cpp", limit_address_space: true },
+        "c" => LocalLanguage { file_name: "main.c", source_extension: "c", limit_address_space: true },
+        "python" => LocalLanguage { file_name: "main.py", source_extension: "py", limit_address_space: true },
+        // The JVM reserves far more address space than it uses, so the heap flag is the limit.
+        "java" => LocalLanguage { file_name: "Main.java", source_extension: "java", limit_address_space: false },
+        _ => return None,
+    };
+
+    Some(language)
+}
+
+impl LocalLanguage {
+    /// Compiles every source file in `files`, which are the names written in the directory.
+    fn compile_command(&self, lang: &str, files: &[String]) -> Option<Vec<String>> {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+        let sources = files
+            .iter()
+            .filter(|name| Path::new(name).extension().is_some_and(|e| e == self.source_extension))
+            .cloned();
+
+        match lang {
+            "cpp" => Some(args(&["g++", "-O2", "-std=c++17", "-o", "main"]).into_iter().chain(sources).collect()),
+            "c" => Some(args(&["gcc", "-O2", "-o", "main"]).into_iter().chain(sources).chain(args(&["-lm"])).collect()),
+            "java" => Some(args(&["javac"]).into_iter().chain(sources).collect()),
+            _ => None,
+        }
+    }
+
+    fn run_command(&self, lang: &str, entry: &str, memory_limit_mb: i32) -> Vec<String> {
+        match lang {
+            "python" => vec!["python3".to_string(), entry.to_string()],
+            "java" => vec![
+                "java".to_string(),
+                format!("-Xmx{}m", memory_limit_mb.max(1)),
+                "-cp".to_string(),
+                ".".to_string(),
+                Path::new(entry).file_stem().and_then(|f| f.to_str()).unwrap_or("Main").to_string(),
+            ],
+            _ => vec!["./main".to_string()],
+        }
+    }
+}
+
+/// The names the files get in the sandbox, entry point first. The entry point
+/// takes the language's default name unless another file already has it.
+fn local_file_names(language: &LocalLanguage, files: &[SourceFile]) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    for file in files {
+        let name = match file.name.as_deref() {
+            Some(name) => Some(
+                Path::new(name)
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .ok_or_else(|| format!("Invalid file name {}", name))?
+                    .to_string(),
+            ),
+            None => None,
+        };
+        names.push(name);
+    }
+
+    let default_taken = names.iter().skip(1).any(|name| name.as_deref() == Some(language.file_name));
+    if let Some(entry) = names.first_mut()
+        && !default_taken
+    {
+        *entry = Some(language.file_name.to_string());
+    }
+
+    let mut unique: Vec<String> = Vec::new();
+    for name in names {
+        let name = name.ok_or("Extra files need a name")?;
+        if unique.contains(&name) {
+            return Err(format!("Two files are named {}", name));
+        }
+        unique.push(name);
+    }
+
+    Ok(unique)
+}
+
+impl LocalBackend {
+    pub fn from_env() -> Self {
+        let workdir = std::env::var("JUDGE_LOCAL_WORKDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| std::env::temp_dir().join("dou-judge"));
+
+        LocalBackend {
+            workdir,
+            namespaces: std::env::var("JUDGE_LOCAL_NAMESPACES").map(|v| v != "false").unwrap_or(true),
+            max_processes: std::env::var("JUDGE_LOCAL_MAX_PROCESSES").ok().and_then(|v| v.parse().ok()).unwrap_or(64),
+            users: (unsafe { libc::geteuid() } == 0).then(|| UserPool {
+                base: std::env::var("JUDGE_LOCAL_UID_BASE").ok().and_then(|v| v.parse().ok()).unwrap_or(60000),
+                count: std::env::var("JUDGE_LOCAL_UID_COUNT").ok().and_then(|v| v.parse().ok()).filter(|&c| c > 0).unwrap_or(1000),
+                next: AtomicU32::new(0),
+            }),
+        }
+    }
+
+    fn sandbox_user(&self) -> Option<SandboxUser> {
+        self.users.as_ref().map(UserPool::next)
+    }
+
+    async fn sandboxed(&self, program: Vec<String>, dir: &Path, stdin: &str, limits: SandboxLimits) -> Result<ResultObject, String> {
+        let dir = dir.to_path_buf();
+        let stdin = stdin.as_bytes().to_vec();
+
+        let result = tokio::task::spawn_blocking(move || run_sandboxed(&program, &dir, &stdin, &limits))
+            .await
+            .map_err(|e| format!("Sandbox task failed: {}", e))?
+            .map_err(|e| format!("Cannot start the sandbox: {}", e))?;
+
+        Ok(to_result_object(result))
+    }
+
+    fn run_setup(&self, artifact: &Artifact, args: &[String]) -> Result<(Vec<String>, SandboxLimits), String> {
+        let source = &artifact.source;
+        let language = local_language(&source.lang)
+            .ok_or_else(|| format!("Language {} is not available on the local judge", source.lang))?;
+
+        let names = local_file_names(&language, &source.files)?;
+        let entry = names.first().ok_or("The submission has no files")?;
+
+        let mut program = language.run_command(&source.lang, entry, source.memory_limit);
+        program.extend(args.iter().cloned());
+
+        let time_limit = source.time_limit.max(1) as u64;
+        let memory_limit = (source.memory_limit.max(1) as u64 + MEMORY_OVERHEAD_MB) * 1024 * 1024;
+
+        let limits = SandboxLimits {
+            cpu_time_ms: time_limit,
+            wall_time_ms: source.wall_time_limit(),
+            memory_bytes: language.limit_address_space.then_some(memory_limit),
+            output_bytes: OUTPUT_LIMIT_BYTES,
+            max_processes: self.max_processes,
+            namespaces: self.namespaces,
+            seccomp: true,
+            user: self.sandbox_user(),
+        };
+
+        Ok((program, limits))
+    }
+
+    /// Every run gets a fresh copy so a test cannot tamper with the artifact.
+    async fn prepare_run_dir(&self, artifact: &Artifact, files: &[SourceFile]) -> Result<PathBuf, String> {
+        let artifact_dir = artifact.workdir.as_ref().ok_or("Artifact was not built by the local judge")?;
+        let run_dir = self.workdir.join(Uuid::new_v4().to_string());
+
+        let from = artifact_dir.clone();
+        let to = run_dir.clone();
+        tokio::task::spawn_blocking(move || copy_dir(&from, &to))
+            .await
+            .map_err(|e| format!("Sandbox task failed: {}", e))?
+            .map_err(|e| format!("Cannot prepare the sandbox directory: {}", e))?;
+
+        if let Err(e) = write_files(&run_dir, files).await {
+            let _ = fs::remove_dir_all(&run_dir).await;
+            return Err(e);
+        }
+
+        Ok(run_dir)
+    }
+
+    async fn compile_in(&self, dir: &Path, source: &CompileStruct) -> Result<Option<ResultObject>, String> {
+        let language = local_language(&source.lang)
+            .ok_or_else(|| format!("Language {} is not available on the local judge", source.lang))?;
+
+        let names = local_file_names(&language, &source.files)?;
+        for (name, file) in names.iter().zip(&source.files) {
+            fs::write(dir.join(name), &file.content)
+                .await
+                .map_err(|e| format!("Cannot write {}: {}", name, e))?;
+        }
+
+        let Some(command) = language.compile_command(&source.lang, &names) else {
+            return Ok(None);
+        };
+
+        let limits = SandboxLimits {
+            cpu_time_ms: COMPILE_TIME_MS,
+            wall_time_ms: COMPILE_TIME_MS * 2,
+            memory_bytes: None,
+            output_bytes: OUTPUT_LIMIT_BYTES,
+            max_processes: self.max_processes,
+            namespaces: self.namespaces,
+            seccomp: false,
+            user: self.sandbox_user(),
+        };
+
+        let result = self.sandboxed(command, dir, "", limits).await?;
+
+        if result.code != Some(0) {
+            return Ok(Some(result));
+        }
+
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl JudgeBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn compile(&self, source: &CompileStruct) -> Result<Compilation, String> {
+        let dir = self.workdir.join(Uuid::new_v4().to_string());
+
+        fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| format!("Cannot create the sandbox directory: {}", e))?;
+
+        match self.compile_in(&dir, source).await {
+            Ok(None) => Ok(Compilation::Ready(Artifact {
+                source: source.clone(),
+                workdir: Some(dir),
+            })),
+            Ok(Some(failed)) => {
+                let _ = fs::remove_dir_all(&dir).await;
+                Ok(Compilation::Failed(failed))
+            }
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn run_with_files(&self, artifact: &Artifact, stdin: &str, args: &[String], files: &[SourceFile]) -> Result<ResultObject, String> {
+        let (program, limits) = self.run_setup(artifact, args)?;
+        let run_dir = self.prepare_run_dir(artifact, files).await?;
+
+        let result = self.sandboxed(program, &run_dir, stdin, limits).await;
+
+        let _ = fs::remove_dir_all(&run_dir).await;
+
+        result
+    }
+
+    async fn run_interactive(&self, artifact: &Artifact, interactor: &Artifact, input: &str, answer: &str) -> Result<InteractiveRun, String> {
+        let (program, limits) = self.run_setup(artifact, &[])?;
+        let interactor_args = ["input.txt", "output.txt", "answer.txt"].map(String::from);
+        let (interactor_program, mut interactor_limits) = self.run_setup(interactor, &interactor_args)?;
+
+        // The interactor must outlive the contestant to give its verdict.
+        interactor_limits.cpu_time_ms = interactor_limits.cpu_time_ms.max(limits.cpu_time_ms) + 1000;
+        interactor_limits.wall_time_ms = interactor_limits.wall_time_ms.max(limits.wall_time_ms) + 1000;
+
+        let files = [
+            SourceFile { name: Some("input.txt".to_string()), content: input.to_string() },
+            SourceFile { name: Some("answer.txt".to_string()), content: answer.to_string() },
+        ];
+
+        let run_dir = self.prepare_run_dir(artifact, &[]).await?;
+        let interactor_dir = match self.prepare_run_dir(interactor, &files).await {
+            Ok(dir) => dir,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&run_dir).await;
+                return Err(e);
+            }
+        };
+
+        let contestant_dir = run_dir.clone();
+        let sandbox_interactor_dir = interactor_dir.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            run_interactive_sandboxed(
+                (&program, &contestant_dir, &limits),
+                (&interactor_program, &sandbox_interactor_dir, &interactor_limits),
+            )
+        })
+        .await;
+
+        let _ = fs::remove_dir_all(&run_dir).await;
+        let _ = fs::remove_dir_all(&interactor_dir).await;
+
+        let (contestant, interactor) = result
+            .map_err(|e| format!("Sandbox task failed: {}", e))?
+            .map_err(|e| format!("Cannot start the sandbox: {}", e))?;
+
+        Ok(InteractiveRun {
+            contestant: to_result_object(contestant),
+            interactor: to_result_object(interactor),
+        })
+    }
+
+    async fn release(&self, artifact: Artifact) {
+        if let Some(dir) = artifact.workdir {
+            let _ = fs::remove_dir_all(&dir).await;
+        }
+    }
+}
+
+async fn write_files(dir: &Path, files: &[SourceFile]) -> Result<(), String> {
+    for file in files {
+        let name = file.name.as_deref().ok_or("Extra files need a name")?;
+        let file_name = Path::new(name)
+            .file_name()
+            .ok_or_else(|| format!("Invalid file name {}", name))?;
+        fs::write(dir.join(file_name), &file.content)
+            .await
+            .map_err(|e| format!("Cannot write {}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn to_result_object(result: SandboxResult) -> ResultObject {
+    ResultObject {
+        stdout: result.stdout,
+        stderr: result.stderr,
+        code: result.exit_code,
+        signal: result.signal,
+        wall_time: Some(result.wall_time_ms),
+        cpu_time: Some(result.cpu_time_ms),
+        memory: Some(result.memory_bytes),
+        status: if result.timed_out {
+            Some("TO".to_string())
+        } else if result.output_exceeded {
+            Some("OL".to_string())
+        } else {
+            None
+        },
+    }
+}
@@ -0,0 +1,15 @@
+pub mod backend;
+pub mod checker;
+pub mod client;
+pub mod diagnostics;
+pub mod grader;
+pub mod languages;
+pub mod local;
+pub mod pipeline;
+pub mod piston;
+pub mod queue;
+pub mod runner;
+pub mod sandbox;
+pub mod status;
+pub mod testdata;
+pub mod worker;
@@ -0,0 +1,423 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use futures::stream::{self, StreamExt};
+use tokio::fs;
+use tokio::sync::Semaphore;
+use crate::judge::backend::{Artifact, Compilation, JudgeBackend};
+use crate::judge::checker::{compile_testlib_program, Checker, CheckerConfig};
+use crate::judge::diagnostics::{sanitize_output, COMPILE_OUTPUT_LIMIT_BYTES, STDERR_LIMIT_BYTES};
+use crate::judge::status::SubmissionStatus;
+use crate::judge::testdata::TestData;
+use crate::judge::worker::env_or;
+use crate::models::compile_models::{CompileStruct, ResultObject};
+use crate::models::result_models::{JudgeOutcome, SubtaskResult, TestCaseResult};
+use crate::models::subtask_models::Subtask;
+
+// Score of a problem without subtasks, all or nothing.
+const FULL_SCORE: i32 = 100;
+
+/// How many test cases run at once: `per_submission` for one submission, and
+/// `slots` shared by every worker so the judge is never sent more than it can take.
+#[derive(Clone)]
+pub struct TestConcurrency {
+    pub per_submission: usize,
+    pub slots: Arc<Semaphore>,
+}
+
+impl TestConcurrency {
+    pub fn from_env() -> Self {
+        let global: usize = env_or("JUDGE_MAX_CONCURRENT_TESTS", 8);
+
+        TestConcurrency {
+            per_submission: env_or("JUDGE_TESTS_PER_SUBMISSION", 4).max(1),
+            slots: Arc::new(Semaphore::new(global.max(1))),
+        }
+    }
+}
+
+/// Everything about the problem that decides how a submission is judged.
+pub struct ProblemSpec {
+    pub tests: Arc<TestData>,
+    pub checker: CheckerConfig,
+    pub interactor_url: Option<String>,
+    pub subtasks_url: Option<String>,
+}
+
+pub async fn compile(
+    backend: &dyn JudgeBackend,
+    source: CompileStruct,
+    problem: &ProblemSpec,
+    run_all_cases: bool,
+    concurrency: &TestConcurrency,
+    status: &SubmissionStatus<'_>,
+) -> Result<JudgeOutcome, String> {
+    let subtasks = match &problem.subtasks_url {
+        Some(subtasks_url) => Some(load_subtasks(subtasks_url).await?),
+        None => None,
+    };
+
+    status.compiling();
+
+    let artifact = match backend.compile(&source).await? {
+        Compilation::Ready(artifact) => artifact,
+        Compilation::Failed(output) => {
+            let compile_output = if output.stderr.is_empty() { output.stdout } else { output.stderr };
+            return Ok(compile_error(sanitize_output(&compile_output, COMPILE_OUTPUT_LIMIT_BYTES)));
+        }
+    };
+
+    let checker = match Checker::build(backend, &problem.checker).await {
+        Ok(checker) => checker,
+        Err(e) => {
+            backend.release(artifact).await;
+            return Err(e);
+        }
+    };
+
+    let interactor = match &problem.interactor_url {
+        Some(interactor_url) => match compile_testlib_program(backend, interactor_url, problem.checker.testlib_version.as_deref()).await {
+            Ok(interactor) => Some(interactor),
+            Err(e) => {
+                checker.release(backend).await;
+                backend.release(artifact).await;
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
+    let judge = CaseJudge {
+        backend,
+        artifact: &artifact,
+        checker: &checker,
+        interactor: interactor.as_ref(),
+        concurrency,
+    };
+
+    let outcome = run_cases(&judge, &problem.tests, subtasks.as_deref(), run_all_cases, status).await;
+
+    if let Some(interactor) = interactor {
+        backend.release(interactor).await;
+    }
+    checker.release(backend).await;
+    backend.release(artifact).await;
+
+    // Backends that compile on every run report a compile error on the first test.
+    if let Ok(outcome) = &outcome
+        && let Some(failed) = outcome.results.iter().find(|result| result.veredict == "CE")
+    {
+        return Ok(compile_error(failed.stderr.clone().unwrap_or_default()));
+    }
+
+    outcome
+}
+
+fn compile_error(compile_output: String) -> JudgeOutcome {
+    JudgeOutcome {
+        veredict: "CE".to_string(),
+        score: 0,
+        results: Vec::new(),
+        subtasks: Vec::new(),
+        compile_output: Some(compile_output),
+    }
+}
+
+struct CaseJudge<'a> {
+    backend: &'a dyn JudgeBackend,
+    artifact: &'a Artifact,
+    checker: &'a Checker,
+    interactor: Option<&'a Artifact>,
+    concurrency: &'a TestConcurrency,
+}
+
+impl CaseJudge<'_> {
+    async fn judge_case(&self, input: &str, expected_stdout: &str) -> Result<(String, ResultObject), String> {
+        let time_limit = self.artifact.source.time_limit;
+        let memory_limit = self.artifact.source.memory_limit;
+
+        if let Some(interactor) = self.interactor {
+            let result = self.backend.run_interactive(self.artifact, interactor, input, expected_stdout).await?;
+            let veredict = interactive_verdict(&result.contestant, &result.interactor, time_limit, memory_limit)?;
+            return Ok((veredict, result.contestant));
+        }
+
+        let run = self.backend.run(self.artifact, input).await?;
+
+        let veredict = match get_verdict(&run, time_limit, memory_limit)? {
+            Some(failure) => failure,
+            None if self.checker.check(self.backend, input, &run.stdout, expected_stdout).await? => "AC".to_string(),
+            None => "WA".to_string(),
+        };
+
+        Ok((veredict, run))
+    }
+}
+
+/// Judges subtask by subtask, running each test case once even when several
+/// subtasks share it. A subtask scores only if all its test cases pass and
+/// every subtask it depends on scored; otherwise it is `SKIPPED`.
+///
+/// The tests of a subtask run in parallel but are read back in order, so the
+/// failure reported is always the lowest-numbered one however the runs finish.
+async fn run_cases(
+    judge: &CaseJudge<'_>,
+    tests: &TestData,
+    subtasks: Option<&[Subtask]>,
+    run_all_cases: bool,
+    status: &SubmissionStatus<'_>,
+) -> Result<JudgeOutcome, String> {
+    let whole_problem;
+    let groups = match subtasks {
+        Some(subtasks) => subtasks,
+        None => {
+            let tests = tests.cases.iter().map(|case| case.id.clone()).collect();
+            whole_problem = [Subtask { id: 1, score: FULL_SCORE, tests, depends_on: Vec::new() }];
+            &whole_problem[..]
+        }
+    };
+
+    let total = groups
+        .iter()
+        .flat_map(|subtask| &subtask.tests)
+        .collect::<HashSet<_>>()
+        .len();
+
+    let mut results: Vec<TestCaseResult> = Vec::new();
+    let mut judged: HashMap<&str, usize> = HashMap::new();
+    let mut subtask_results: Vec<SubtaskResult> = Vec::new();
+
+    for subtask in groups {
+        let blocked = subtask.depends_on.iter().any(|dependency| {
+            !subtask_results
+                .iter()
+                .any(|done| done.subtask_id == *dependency && done.veredict == "AC")
+        });
+
+        let mut veredict = if blocked { "SKIPPED" } else { "AC" }.to_string();
+
+        if !blocked {
+            let mut queued = HashSet::new();
+            let pending: Vec<&String> = subtask
+                .tests
+                .iter()
+                .filter(|test| !judged.contains_key(test.as_str()) && queued.insert(test.as_str()))
+                .collect();
+
+            // How many of the pending tests may start: those up to the first failure.
+            let stop = AtomicUsize::new(usize::MAX);
+            let runs: Vec<_> = pending
+                .into_iter()
+                .enumerate()
+                .map(|(position, test)| judge_test_in_parallel(judge, tests, test, position, &stop, run_all_cases))
+                .collect();
+            let mut runs = stream::iter(runs).buffered(judge.concurrency.per_submission);
+
+            let mut error = None;
+
+            for test in &subtask.tests {
+                let index = match judged.get(test.as_str()) {
+                    Some(index) => *index,
+                    None => match runs.next().await {
+                        Some(Some(Ok(result))) => {
+                            status.running(results.len() + 1, total);
+                            results.push(result);
+                            judged.insert(test, results.len() - 1);
+                            results.len() - 1
+                        }
+                        Some(Some(Err(e))) => {
+                            error = Some(e);
+                            break;
+                        }
+                        // Only tests past a failure are skipped, and that failure was
+                        // read first; a missing result never counts as accepted anyway.
+                        Some(None) | None => {
+                            if veredict == "AC" {
+                                veredict = "SKIPPED".to_string();
+                            }
+                            break;
+                        }
+                    },
+                };
+
+                let result_case = &results[index].veredict;
+                if result_case != "AC" {
+                    if veredict == "AC" {
+                        veredict = result_case.clone();
+                    }
+                    if !run_all_cases {
+                        break;
+                    }
+                }
+            }
+
+            // Runs already started finish so their sandboxes are cleaned up,
+            // but their results are past the first failure and not reported.
+            stop.store(0, Ordering::Relaxed);
+            while runs.next().await.is_some() {}
+
+            if let Some(e) = error {
+                return Err(e);
+            }
+        }
+
+        subtask_results.push(SubtaskResult {
+            subtask_id: subtask.id,
+            earned_score: if veredict == "AC" { subtask.score } else { 0 },
+            score: subtask.score,
+            veredict,
+        });
+    }
+
+    let veredict = results
+        .iter()
+        .find(|result| result.veredict != "AC")
+        .map(|result| result.veredict.clone())
+        .unwrap_or_else(|| "AC".to_string());
+    let score = subtask_results.iter().map(|subtask| subtask.earned_score).sum();
+
+    Ok(JudgeOutcome {
+        veredict,
+        score,
+        results,
+        subtasks: if subtasks.is_some() { subtask_results } else { Vec::new() },
+        compile_output: None,
+    })
+}
+
+// Returns `None` for a test that never started because an earlier one failed.
+async fn judge_test_in_parallel(
+    judge: &CaseJudge<'_>,
+    tests: &TestData,
+    test: &str,
+    position: usize,
+    stop: &AtomicUsize,
+    run_all_cases: bool,
+) -> Option<Result<TestCaseResult, String>> {
+    if position >= stop.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let _slot = match judge.concurrency.slots.acquire().await {
+        Ok(slot) => slot,
+        Err(e) => return Some(Err(format!("Test slots closed: {}", e))),
+    };
+
+    if position >= stop.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let result = judge_test(judge, tests, test).await;
+
+    if result.as_ref().map_or(true, |result| result.veredict != "AC" && !run_all_cases) {
+        stop.fetch_min(position + 1, Ordering::Relaxed);
+    }
+
+    Some(result)
+}
+
+async fn judge_test(
+    judge: &CaseJudge<'_>,
+    tests: &TestData,
+    test: &str,
+) -> Result<TestCaseResult, String> {
+    let case = tests.get(test).ok_or_else(|| format!("Test case {} doesn't exist", test))?;
+
+    println!("Caso: {}", test);
+
+    let (veredict, run) = judge.judge_case(&case.input, &case.output).await?;
+
+    // Students know the input of sample tests, so what their program wrote to
+    // stderr there helps them and gives nothing away. Hidden tests show nothing.
+    // A compile error carries the compiler output, which the submitter sees anyway.
+    let stderr = if veredict == "CE" {
+        Some(sanitize_output(&run.stderr, COMPILE_OUTPUT_LIMIT_BYTES))
+    } else {
+        (case.sample && veredict != "AC" && !run.stderr.is_empty()).then(|| sanitize_output(&run.stderr, STDERR_LIMIT_BYTES))
+    };
+
+    Ok(TestCaseResult {
+        test_case: test.to_string(),
+        veredict,
+        wall_time: run.wall_time,
+        memory: run.memory,
+        signal: run.signal,
+        exit_code: run.code,
+        stderr,
+    })
+}
+
+async fn load_subtasks(subtasks_url: &str) -> Result<Vec<Subtask>, String> {
+    let content = fs::read_to_string(subtasks_url)
+        .await
+        .map_err(|e| format!("Error reading {}: {}", subtasks_url, e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Invalid subtasks file {}: {}", subtasks_url, e))
+}
+
+/// What went wrong with a run, if anything: `TLE`, `OLE`, `MLE` or `RTE`, or
+/// `CE` from a backend that compiles on every run (the output is in `stderr`).
+/// The judge's status and CPU time decide when it reports them; `memory` is in
+/// bytes and `memory_limit` in MB.
+// Messages of a runtime whose allocation failed. A program capped by its
+// address space sees its allocations fail and usually dies before its
+// resident memory reaches the limit, so this is all there is to tell an MLE.
+const ALLOCATION_FAILURES: [&str; 4] = [
+    "std::bad_alloc",
+    "MemoryError",
+    "java.lang.OutOfMemoryError",
+    "Cannot allocate memory",
+];
+
+pub fn get_verdict(run: &ResultObject, time_limit: i32, memory_limit: i32) -> Result<Option<String>, String> {
+    let status = run.status.as_deref();
+    let signal = run.signal.as_deref();
+
+    if status == Some("XX") {
+        return Err(format!("The judge could not run the program: {}", run.stderr));
+    }
+
+    if status == Some("CE") {
+        return Ok(Some("CE".to_string()));
+    }
+
+    let time_used = run.cpu_time.or(run.wall_time);
+    let memory_limit_bytes = memory_limit as i64 * 1024 * 1024;
+    let failed = signal.is_some() || run.code != Some(0);
+    let allocation_failed = failed && ALLOCATION_FAILURES.iter().any(|message| run.stderr.contains(message));
+
+    let veredict = if status == Some("TO") || signal == Some("SIGXCPU") || time_used.is_some_and(|time| time > time_limit) {
+        "TLE"
+    } else if matches!(status, Some("OL") | Some("EL")) || signal == Some("SIGXFSZ") {
+        "OLE"
+    } else if run.memory.is_some_and(|memory| memory as i64 > memory_limit_bytes) || allocation_failed {
+        "MLE"
+    } else if failed {
+        "RTE"
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(veredict.to_string()))
+}
+
+// The interactor's exit code decides, except that a contestant killed for
+// time or memory keeps that verdict: the interactor only saw a closed pipe.
+fn interactive_verdict(contestant: &ResultObject, interactor: &ResultObject, time_limit: i32, memory_limit: i32) -> Result<String, String> {
+    let contestant_failure = get_verdict(contestant, time_limit, memory_limit)?;
+
+    if let Some(failure) = &contestant_failure
+        && (failure == "TLE" || failure == "MLE")
+    {
+        return Ok(failure.clone());
+    }
+
+    match interactor.code {
+        Some(0) => Ok(contestant_failure.unwrap_or_else(|| "AC".to_string())),
+        Some(1) | Some(2) => Ok("WA".to_string()),
+        _ => Err(format!(
+            "Interactor failed (code {:?}, signal {:?}): {}",
+            interactor.code, interactor.signal, interactor.stderr
+        )),
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use crate::judge::backend::{Artifact, Compilation, JudgeBackend};
+use crate::judge::client::{JudgeClient, JudgeClientConfig};
+use crate::models::compile_models::{CompileStruct, CompileRequest, CompileResponse, ResultObject, SourceFile};
+use crate::models::language_models::Runtime;
+
+// Piston only exposes a single compile-and-run call and keeps nothing between
+// calls, so there is no build to reuse: compiling once per submission is out of
+// scope for this backend and every run sends the sources and compiles them
+// again, checkers and interactors included. Use the local backend
+// (docker-compose.local-judge.yaml) to build once and run the binary per test.
+// The compile step sends nothing, and a compile error comes back from the first
+// run as a result with the `CE` status.
+const COMPILE_TIMEOUT_MS: u64 = 10_000;
+
+// The JVM reserves far more address space than it uses, so its memory is only
+// checked against what the run reports.
+const UNLIMITED_ADDRESS_SPACE: [&str; 1] = ["java"];
+
+pub struct PistonBackend {
+    client: JudgeClient,
+    judge_url: String,
+}
+
+impl PistonBackend {
+    pub fn new(judge_url: String, config: JudgeClientConfig) -> Self {
+        PistonBackend {
+            client: JudgeClient::new(config),
+            judge_url,
+        }
+    }
+
+    async fn execute(&self, source: &CompileStruct, stdin: &str, args: &[String], files: &[SourceFile]) -> Result<CompileResponse, String> {
+        let mut request_files = source.files.clone();
+        request_files.extend(files.iter().cloned());
+
+        let run_timeout = source.wall_time_limit();
+        let limit_memory = !UNLIMITED_ADDRESS_SPACE.contains(&source.lang.as_str());
+
+        let request = CompileRequest {
+            language: source.lang.clone(),
+            version: source.version.clone(),
+            files: request_files,
+            stdin: stdin.to_string(),
+            args: args.to_vec(),
+            compile_timeout: Some(COMPILE_TIMEOUT_MS),
+            run_timeout: Some(run_timeout),
+            run_cpu_time: Some(source.time_limit.max(1) as u64),
+            run_memory_limit: limit_memory.then(|| source.memory_limit.max(1) as i64 * 1024 * 1024),
+        };
+
+        let judge_time = Duration::from_millis(COMPILE_TIMEOUT_MS + run_timeout);
+
+        self.client
+            .send_json(|client| client.post(&self.judge_url).json(&request), judge_time)
+            .await
+    }
+}
+
+#[async_trait]
+impl JudgeBackend for PistonBackend {
+    fn name(&self) -> &'static str {
+        "piston"
+    }
+
+    async fn compile(&self, source: &CompileStruct) -> Result<Compilation, String> {
+        Ok(Compilation::Ready(Artifact {
+            source: source.clone(),
+            workdir: None,
+        }))
+    }
+
+    async fn run_with_files(&self, artifact: &Artifact, stdin: &str, args: &[String], files: &[SourceFile]) -> Result<ResultObject, String> {
+        let response = self.execute(&artifact.source, stdin, args, files).await?;
+
+        if let Some(compile) = response.compile
+            && compile.code != Some(0)
+        {
+            let output = if compile.stderr.is_empty() { compile.stdout } else { compile.stderr };
+            return Ok(ResultObject {
+                stdout: String::new(),
+                stderr: output,
+                status: Some("CE".to_string()),
+                ..compile
+            });
+        }
+
+        response.run.ok_or_else(|| "The judge returned no run result".to_string())
+    }
+
+    // JUDGE_IP points at `/api/v2/execute`; the runtimes list lives next to it.
+    async fn runtimes(&self) -> Result<Vec<Runtime>, String> {
+        let runtimes_url = match self.judge_url.trim_end_matches('/').strip_suffix("/execute") {
+            Some(base) => format!("{}/runtimes", base),
+            None => return Err(format!("Cannot find the runtimes endpoint for {}", self.judge_url)),
+        };
+
+        self.client
+            .send_json(|client| client.get(&runtimes_url), Duration::ZERO)
+            .await
+            .map_err(|e| format!("Cannot list the runtimes at {}: {}", runtimes_url, e))
+    }
+
+    fn is_available(&self) -> bool {
+        self.client.is_available()
+    }
+}
@@ -0,0 +1,452 @@
+use std::sync::Arc;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use tokio::sync::Notify;
+use crate::models::job_models::{JudgeJob, RejudgeFilter};
+use crate::models::result_models::JudgeOutcome;
+
+/// What became of a job its worker gave up on.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Release {
+    Requeued,
+    Failed,
+    /// The job was swept as stale and belongs to another attempt now.
+    LeaseLost,
+}
+
+#[derive(Clone, Default)]
+pub struct JudgeQueue {
+    notify: Arc<Notify>,
+}
+
+impl JudgeQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wake_worker(&self) {
+        self.notify.notify_one();
+    }
+
+    pub async fn wait_for_job(&self) {
+        self.notify.notified().await;
+    }
+}
+
+pub async fn enqueue(tx: &mut Transaction<'_, Postgres>, submission_id: i32, lang: &str, version: &str) -> Result<i32, sqlx::Error> {
+    let query = "
+        INSERT INTO JUDGE_JOBS(
+            SUBMISSION_ID,
+            JOB_LANGUAGE,
+            JOB_LANGUAGE_VERSION
+        )
+        VALUES ($1, $2, $3)
+        RETURNING JOB_ID
+    ";
+
+    let row = sqlx::query(query)
+        .bind(submission_id)
+        .bind(lang)
+        .bind(version)
+        .fetch_one(&mut **tx)
+        .await?;
+
+    Ok(row.get("job_id"))
+}
+
+/// Queues the submissions matching `filter` again with the version of their
+/// language in LANGUAGES now, not the one of their last job, which language
+/// discovery may have replaced. Submissions that already have a job waiting
+/// are left alone.
+pub async fn enqueue_rejudge(pool: &PgPool, filter: &RejudgeFilter, requested_by: i32) -> Result<Vec<(i32, i32)>, sqlx::Error> {
+    let query = "
+        WITH QUEUED AS (
+            INSERT INTO JUDGE_JOBS(
+                SUBMISSION_ID,
+                JOB_LANGUAGE,
+                JOB_LANGUAGE_VERSION,
+                JOB_REQUESTED_BY
+            )
+            SELECT DISTINCT ON (J.SUBMISSION_ID)
+                J.SUBMISSION_ID,
+                L.LANGUAGE_NAME,
+                L.LANGUAGE_VERSION,
+                $5
+            FROM JUDGE_JOBS J
+            JOIN SUBMISSIONS S ON S.SUBMISSION_ID = J.SUBMISSION_ID
+            JOIN LANGUAGES L ON L.LANGUAGE_NAME = S.SUBMISSION_LANGUAGE
+            WHERE ($1::INT IS NULL OR S.SUBMISSION_ID = $1)
+            AND ($2::INT IS NULL OR S.PROBLEM_ID = $2)
+            AND ($3::TIMESTAMPTZ IS NULL OR S.SUBMISSION_CREATED_AT >= $3)
+            AND ($4::TIMESTAMPTZ IS NULL OR S.SUBMISSION_CREATED_AT < $4)
+            AND NOT EXISTS (
+                SELECT 1
+                FROM JUDGE_JOBS Q
+                WHERE Q.SUBMISSION_ID = J.SUBMISSION_ID
+                AND Q.JOB_STATUS = 'QUEUED'
+            )
+            ORDER BY J.SUBMISSION_ID ASC, J.JOB_ID DESC
+            RETURNING SUBMISSION_ID
+        )
+        SELECT Q.SUBMISSION_ID, S.USER_ID
+        FROM QUEUED Q
+        JOIN SUBMISSIONS S ON S.SUBMISSION_ID = Q.SUBMISSION_ID
+        ORDER BY Q.SUBMISSION_ID ASC
+    ";
+
+    let rows = sqlx::query(query)
+        .bind(filter.submission_id)
+        .bind(filter.problem_id)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(requested_by)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("submission_id"), row.get("user_id")))
+        .collect())
+}
+
+pub async fn claim_job(pool: &PgPool) -> Result<Option<JudgeJob>, sqlx::Error> {
+    let query = "
+        UPDATE JUDGE_JOBS
+        SET JOB_STATUS = 'RUNNING',
+            JOB_ATTEMPTS = JOB_ATTEMPTS + 1,
+            JOB_LOCKED_AT = NOW(),
+            JOB_HEARTBEAT_AT = NOW()
+        FROM SUBMISSIONS S
+        WHERE S.SUBMISSION_ID = JUDGE_JOBS.SUBMISSION_ID
+        AND JUDGE_JOBS.JOB_ID = (
+            SELECT J.JOB_ID
+            FROM JUDGE_JOBS J
+            WHERE J.JOB_STATUS = 'QUEUED'
+            AND (J.JOB_RETRY_AT IS NULL OR J.JOB_RETRY_AT <= NOW())
+            AND NOT EXISTS (
+                SELECT 1
+                FROM JUDGE_JOBS R
+                WHERE R.SUBMISSION_ID = J.SUBMISSION_ID
+                AND R.JOB_STATUS = 'RUNNING'
+            )
+            ORDER BY J.JOB_ID ASC
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING
+            JUDGE_JOBS.JOB_ID,
+            JUDGE_JOBS.SUBMISSION_ID,
+            S.USER_ID,
+            JUDGE_JOBS.JOB_LANGUAGE,
+            JUDGE_JOBS.JOB_LANGUAGE_VERSION,
+            JUDGE_JOBS.JOB_ATTEMPTS
+    ";
+
+    let row = sqlx::query(query).fetch_optional(pool).await?;
+
+    Ok(row.map(|row| JudgeJob {
+        job_id: row.get("job_id"),
+        submission_id: row.get("submission_id"),
+        user_id: row.get("user_id"),
+        lang: row.get("job_language"),
+        version: row.get("job_language_version"),
+        attempts: row.get("job_attempts"),
+    }))
+}
+
+/// Renews the worker's lease on the job. Returns false once the job was swept
+/// as stale, after which nothing this worker does with it is saved.
+pub async fn heartbeat(pool: &PgPool, job: &JudgeJob) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE JUDGE_JOBS SET JOB_HEARTBEAT_AT = NOW() WHERE JOB_ID = $1 AND JOB_STATUS = 'RUNNING' AND JOB_ATTEMPTS = $2")
+        .bind(job.job_id)
+        .bind(job.attempts)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Locks the job if this attempt still owns it. The attempt number fences off
+// a worker that was swept as stale but kept judging.
+async fn hold_lease(tx: &mut Transaction<'_, Postgres>, job: &JudgeJob) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query("SELECT JOB_ID FROM JUDGE_JOBS WHERE JOB_ID = $1 AND JOB_STATUS = 'RUNNING' AND JOB_ATTEMPTS = $2 FOR UPDATE")
+        .bind(job.job_id)
+        .bind(job.attempts)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// Saves the outcome. Returns false if the job's lease was lost, in which
+/// case nothing is saved.
+pub async fn complete_job(pool: &PgPool, job: &JudgeJob, outcome: &JudgeOutcome) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    if !hold_lease(&mut tx, job).await? {
+        return Ok(false);
+    }
+
+    record_verdict(&mut tx, job, &outcome.veredict, outcome.score).await?;
+
+    let update_submission = "
+        UPDATE SUBMISSIONS
+        SET SUBMISSION_ANSWER_CODE = $1,
+            SUBMISSION_COMPILE_OUTPUT = $2,
+            SUBMISSION_EARNED_SCORE = $3,
+            SUBMISSION_TIME = $4,
+            SUBMISSION_MEMORY = $5,
+            SUBMISSION_JUDGED_AT = NOW()
+        WHERE SUBMISSION_ID = $6
+    ";
+
+    let max_time = outcome.results.iter().filter_map(|result| result.wall_time).max();
+    let max_memory = outcome.results.iter().filter_map(|result| result.memory).max();
+
+    sqlx::query(update_submission)
+        .bind(&outcome.veredict)
+        .bind(&outcome.compile_output)
+        .bind(outcome.score)
+        .bind(max_time)
+        .bind(max_memory)
+        .bind(job.submission_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM SUBMISSION_RESULTS WHERE SUBMISSION_ID = $1")
+        .bind(job.submission_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let insert_result = "
+        INSERT INTO SUBMISSION_RESULTS(
+            SUBMISSION_ID,
+            RESULT_TEST_CASE,
+            RESULT_ORDER,
+            RESULT_VERDICT,
+            RESULT_WALL_TIME,
+            RESULT_MEMORY,
+            RESULT_SIGNAL,
+            RESULT_EXIT_CODE,
+            RESULT_STDERR
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+    ";
+
+    for (order, result) in outcome.results.iter().enumerate() {
+        sqlx::query(insert_result)
+            .bind(job.submission_id)
+            .bind(&result.test_case)
+            .bind(order as i32 + 1)
+            .bind(&result.veredict)
+            .bind(result.wall_time)
+            .bind(result.memory)
+            .bind(&result.signal)
+            .bind(result.exit_code)
+            .bind(&result.stderr)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM SUBMISSION_SUBTASK_SCORES WHERE SUBMISSION_ID = $1")
+        .bind(job.submission_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let insert_subtask = "
+        INSERT INTO SUBMISSION_SUBTASK_SCORES(
+            SUBMISSION_ID,
+            SUBTASK_ID,
+            SUBTASK_ORDER,
+            SUBTASK_VERDICT,
+            SUBTASK_SCORE,
+            SUBTASK_EARNED_SCORE
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+    ";
+
+    for (order, subtask) in outcome.subtasks.iter().enumerate() {
+        sqlx::query(insert_subtask)
+            .bind(job.submission_id)
+            .bind(subtask.subtask_id)
+            .bind(order as i32 + 1)
+            .bind(&subtask.veredict)
+            .bind(subtask.score)
+            .bind(subtask.earned_score)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("UPDATE JUDGE_JOBS SET JOB_STATUS = 'DONE', JOB_FINISHED_AT = NOW() WHERE JOB_ID = $1")
+        .bind(job.job_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Puts the job back in the queue, or gives up and stores an `IE` veredict
+/// once `max_attempts` is reached.
+pub async fn fail_job(pool: &PgPool, job: &JudgeJob, error: &str, max_attempts: i32) -> Result<Release, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    if !hold_lease(&mut tx, job).await? {
+        return Ok(Release::LeaseLost);
+    }
+
+    let release = release_job(&mut tx, job, error, max_attempts).await?;
+    tx.commit().await?;
+
+    Ok(release)
+}
+
+// The job must be locked by `tx`.
+async fn release_job(tx: &mut Transaction<'_, Postgres>, job: &JudgeJob, error: &str, max_attempts: i32) -> Result<Release, sqlx::Error> {
+    if job.attempts < max_attempts {
+        sqlx::query("UPDATE JUDGE_JOBS SET JOB_STATUS = 'QUEUED', JOB_LAST_ERROR = $1, JOB_LOCKED_AT = NULL, JOB_HEARTBEAT_AT = NULL WHERE JOB_ID = $2")
+            .bind(error)
+            .bind(job.job_id)
+            .execute(&mut **tx)
+            .await?;
+        return Ok(Release::Requeued);
+    }
+
+    record_verdict(tx, job, "IE", 0).await?;
+
+    sqlx::query("UPDATE SUBMISSIONS SET SUBMISSION_ANSWER_CODE = 'IE', SUBMISSION_EARNED_SCORE = 0, SUBMISSION_JUDGED_AT = NOW() WHERE SUBMISSION_ID = $1")
+        .bind(job.submission_id)
+        .execute(&mut **tx)
+        .await?;
+
+    sqlx::query("UPDATE JUDGE_JOBS SET JOB_STATUS = 'FAILED', JOB_LAST_ERROR = $1, JOB_FINISHED_AT = NOW() WHERE JOB_ID = $2")
+        .bind(error)
+        .bind(job.job_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(Release::Failed)
+}
+
+/// The judge could not be reached: the submission shows `IE` until the job,
+/// queued again after `retry_secs` without using up an attempt, judges it.
+pub async fn defer_job(pool: &PgPool, job: &JudgeJob, error: &str, retry_secs: i64) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    if !hold_lease(&mut tx, job).await? {
+        return Ok(false);
+    }
+
+    let current: String = sqlx::query("SELECT SUBMISSION_ANSWER_CODE FROM SUBMISSIONS WHERE SUBMISSION_ID = $1")
+        .bind(job.submission_id)
+        .fetch_one(&mut *tx)
+        .await?
+        .get("submission_answer_code");
+
+    if current != "IE" {
+        record_verdict(&mut tx, job, "IE", 0).await?;
+
+        sqlx::query("UPDATE SUBMISSIONS SET SUBMISSION_ANSWER_CODE = 'IE', SUBMISSION_EARNED_SCORE = 0, SUBMISSION_JUDGED_AT = NOW() WHERE SUBMISSION_ID = $1")
+            .bind(job.submission_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let requeue = "
+        UPDATE JUDGE_JOBS
+        SET JOB_STATUS = 'QUEUED',
+            JOB_ATTEMPTS = JOB_ATTEMPTS - 1,
+            JOB_LAST_ERROR = $1,
+            JOB_LOCKED_AT = NULL,
+            JOB_HEARTBEAT_AT = NULL,
+            JOB_RETRY_AT = NOW() + make_interval(secs => $2)
+        WHERE JOB_ID = $3
+    ";
+
+    sqlx::query(requeue)
+        .bind(error)
+        .bind(retry_secs as f64)
+        .bind(job.job_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+// Must run before the submission is updated, so the previous verdict is still there.
+async fn record_verdict(tx: &mut Transaction<'_, Postgres>, job: &JudgeJob, veredict: &str, score: i32) -> Result<(), sqlx::Error> {
+    let query = "
+        INSERT INTO SUBMISSION_VERDICT_HISTORY(
+            SUBMISSION_ID,
+            JOB_ID,
+            HISTORY_PREVIOUS_VERDICT,
+            HISTORY_PREVIOUS_SCORE,
+            HISTORY_VERDICT,
+            HISTORY_SCORE
+        )
+        SELECT SUBMISSION_ID, $2, SUBMISSION_ANSWER_CODE, SUBMISSION_EARNED_SCORE, $3, $4
+        FROM SUBMISSIONS
+        WHERE SUBMISSION_ID = $1
+    ";
+
+    sqlx::query(query)
+        .bind(job.submission_id)
+        .bind(job.job_id)
+        .bind(veredict)
+        .bind(score)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Jobs whose worker stopped renewing the lease for `stale_secs` (service
+/// restart, crash, panic) count that run as a failed attempt: they are queued
+/// again, or fail with `IE` once `max_attempts` is used up.
+pub async fn sweep_stale_jobs(pool: &PgPool, stale_secs: i64, max_attempts: i32) -> Result<Vec<(JudgeJob, Release)>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    // The lease is checked under the row lock, so a heartbeat that lands
+    // first keeps the job, and replicas sweeping at once skip each other's rows.
+    let query = "
+        SELECT
+            J.JOB_ID,
+            J.SUBMISSION_ID,
+            S.USER_ID,
+            J.JOB_LANGUAGE,
+            J.JOB_LANGUAGE_VERSION,
+            J.JOB_ATTEMPTS
+        FROM JUDGE_JOBS J
+        JOIN SUBMISSIONS S ON S.SUBMISSION_ID = J.SUBMISSION_ID
+        WHERE J.JOB_STATUS = 'RUNNING'
+        AND COALESCE(J.JOB_HEARTBEAT_AT, J.JOB_LOCKED_AT) < NOW() - make_interval(secs => $1)
+        ORDER BY J.JOB_ID ASC
+        FOR UPDATE OF J SKIP LOCKED
+    ";
+
+    let rows = sqlx::query(query)
+        .bind(stale_secs as f64)
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut swept = Vec::new();
+    for row in rows {
+        let job = JudgeJob {
+            job_id: row.get("job_id"),
+            submission_id: row.get("submission_id"),
+            user_id: row.get("user_id"),
+            lang: row.get("job_language"),
+            version: row.get("job_language_version"),
+            attempts: row.get("job_attempts"),
+        };
+
+        let release = release_job(&mut tx, &job, "The worker judging it stopped responding", max_attempts).await?;
+        swept.push((job, release));
+    }
+
+    tx.commit().await?;
+
+    Ok(swept)
+}
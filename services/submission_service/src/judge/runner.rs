@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use crate::judge::backend::{Compilation, JudgeBackend};
+use crate::judge::diagnostics::{sanitize_output, truncate, COMPILE_OUTPUT_LIMIT_BYTES};
+use crate::judge::pipeline::get_verdict;
+use crate::models::compile_models::CompileStruct;
+use crate::models::run_models::RunResult;
+
+const RUN_OUTPUT_LIMIT_BYTES: usize = 64 * 1024;
+
+/// Runs code against custom input outside the judge queue, nothing is stored.
+/// Since these runs skip the queue, `slots` caps how many hit the judge at once.
+#[derive(Clone)]
+pub struct Runner {
+    backend: Arc<dyn JudgeBackend>,
+    slots: Arc<Semaphore>,
+}
+
+impl Runner {
+    pub fn new(backend: Arc<dyn JudgeBackend>, max_concurrent_runs: usize) -> Self {
+        Runner {
+            backend,
+            slots: Arc::new(Semaphore::new(max_concurrent_runs.max(1))),
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.backend.is_available()
+    }
+
+    pub async fn run(&self, source: CompileStruct, stdin: &str) -> Result<RunResult, String> {
+        let _slot = self.slots.acquire().await.map_err(|e| format!("Runner closed: {}", e))?;
+
+        let time_limit = source.time_limit;
+        let memory_limit = source.memory_limit;
+
+        let artifact = match self.backend.compile(&source).await? {
+            Compilation::Ready(artifact) => artifact,
+            Compilation::Failed(output) => {
+                let compile_output = if output.stderr.is_empty() { output.stdout } else { output.stderr };
+                return Ok(compile_error(&compile_output, time_limit, memory_limit));
+            }
+        };
+
+        let run = self.backend.run(&artifact, stdin).await;
+        self.backend.release(artifact).await;
+        let run = run?;
+
+        let status = get_verdict(&run, time_limit, memory_limit)?.unwrap_or_else(|| "OK".to_string());
+
+        // Backends that compile on every run report compile errors here.
+        if status == "CE" {
+            return Ok(compile_error(&run.stderr, time_limit, memory_limit));
+        }
+
+        Ok(RunResult {
+            status,
+            compile_output: None,
+            stdout: truncate(run.stdout, RUN_OUTPUT_LIMIT_BYTES),
+            stderr: truncate(run.stderr, RUN_OUTPUT_LIMIT_BYTES),
+            exit_code: run.code,
+            signal: run.signal,
+            wall_time: run.wall_time,
+            cpu_time: run.cpu_time,
+            memory: run.memory,
+            time_limit,
+            memory_limit,
+        })
+    }
+}
+
+fn compile_error(compile_output: &str, time_limit: i32, memory_limit: i32) -> RunResult {
+    RunResult {
+        status: "CE".to_string(),
+        compile_output: Some(sanitize_output(compile_output, COMPILE_OUTPUT_LIMIT_BYTES)),
+        stdout: String::new(),
+        stderr: String::new(),
+        exit_code: None,
+        signal: None,
+        wall_time: None,
+        cpu_time: None,
+        memory: None,
+        time_limit,
+        memory_limit,
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::sync::{broadcast, mpsc};
+use crate::models::status_models::StatusEvent;
+
+// Slow subscribers skip events instead of holding back the judge.
+const STATUS_CHANNEL_CAPACITY: usize = 1024;
+const STATUS_NOTIFY_CHANNEL: &str = "submission_status";
+const LISTEN_RETRY: Duration = Duration::from_secs(5);
+
+/// Fans out judging progress to the open status streams. Events go through
+/// Postgres NOTIFY and come back from a listener, so every replica sees the
+/// events of every worker, its own included.
+#[derive(Clone)]
+pub struct StatusBroadcaster {
+    sender: broadcast::Sender<StatusEvent>,
+    outgoing: mpsc::UnboundedSender<StatusEvent>,
+}
+
+impl StatusBroadcaster {
+    pub fn new(pool: PgPool) -> Self {
+        let (sender, _) = broadcast::channel(STATUS_CHANNEL_CAPACITY);
+        let (outgoing, pending) = mpsc::unbounded_channel();
+
+        tokio::spawn(notify_events(pool.clone(), pending));
+        tokio::spawn(listen_events(pool, sender.clone()));
+
+        StatusBroadcaster { sender, outgoing }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StatusEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, event: StatusEvent) {
+        let _ = self.outgoing.send(event);
+    }
+
+    pub fn queued(&self, submission_id: i32, user_id: i32) {
+        self.publish(StatusEvent::new(submission_id, user_id, "QUEUED"));
+    }
+
+    pub fn finished(&self, submission_id: i32, user_id: i32, veredict: &str, score: i32) {
+        self.publish(StatusEvent {
+            veredict: Some(veredict.to_string()),
+            score: Some(score),
+            ..StatusEvent::new(submission_id, user_id, "FINISHED")
+        });
+    }
+
+    pub fn for_submission(&self, submission_id: i32, user_id: i32) -> SubmissionStatus<'_> {
+        SubmissionStatus {
+            broadcaster: self,
+            submission_id,
+            user_id,
+        }
+    }
+}
+
+/// Progress reporter handed to the pipeline while it judges one submission.
+pub struct SubmissionStatus<'a> {
+    broadcaster: &'a StatusBroadcaster,
+    submission_id: i32,
+    user_id: i32,
+}
+
+impl SubmissionStatus<'_> {
+    pub fn compiling(&self) {
+        self.broadcaster.publish(StatusEvent::new(self.submission_id, self.user_id, "COMPILING"));
+    }
+
+    pub fn running(&self, test: usize, total: usize) {
+        self.broadcaster.publish(StatusEvent {
+            test: Some(test),
+            total: Some(total),
+            ..StatusEvent::new(self.submission_id, self.user_id, "RUNNING")
+        });
+    }
+}
+
+// One task sends every notification, so they arrive in the order they were published.
+async fn notify_events(pool: PgPool, mut pending: mpsc::UnboundedReceiver<StatusEvent>) {
+    while let Some(event) = pending.recv().await {
+        let Ok(payload) = serde_json::to_string(&event) else {
+            continue;
+        };
+
+        if let Err(e) = sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(STATUS_NOTIFY_CHANNEL)
+            .bind(&payload)
+            .execute(&pool)
+            .await
+        {
+            eprintln!("Cannot publish the status of submission {}: {}", event.submission_id, e);
+        }
+    }
+}
+
+// `recv` reconnects by itself after a dropped connection; notifications sent
+// in between are lost.
+async fn listen_events(pool: PgPool, sender: broadcast::Sender<StatusEvent>) {
+    loop {
+        let mut listener = match PgListener::connect_with(&pool).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Cannot listen for status events: {}", e);
+                tokio::time::sleep(LISTEN_RETRY).await;
+                continue;
+            }
+        };
+
+        if let Err(e) = listener.listen(STATUS_NOTIFY_CHANNEL).await {
+            eprintln!("Cannot listen for status events: {}", e);
+            tokio::time::sleep(LISTEN_RETRY).await;
+            continue;
+        }
+
+        loop {
+            match listener.recv().await {
+                Ok(notification) => match serde_json::from_str::<StatusEvent>(notification.payload()) {
+                    // No open streams is not an error.
+                    Ok(event) => {
+                        let _ = sender.send(event);
+                    }
+                    Err(e) => eprintln!("Invalid status event: {}", e),
+                },
+                Err(e) => {
+                    eprintln!("Status listener disconnected: {}", e);
+                    tokio::time::sleep(LISTEN_RETRY).await;
+                }
+            }
+        }
+    }
+}
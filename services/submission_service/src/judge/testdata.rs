@@ -0,0 +1,203 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::time::SystemTime;
+use tokio::fs;
+
+/// One input/output pair. `id` is the file name without extension, which stays
+/// the same between runs and is what verdicts report.
+pub struct TestCase {
+    pub id: String,
+    pub input: String,
+    pub output: String,
+    pub sample: bool,
+}
+
+/// The test cases of a problem, samples first and then in natural order
+/// (`1`, `2`, …, `10`).
+pub struct TestData {
+    pub cases: Vec<TestCase>,
+}
+
+impl TestData {
+    pub fn get(&self, id: &str) -> Option<&TestCase> {
+        self.cases.iter().find(|case| case.id == id)
+    }
+}
+
+// Names, sizes and modification times of every file in both folders. When it
+// changes the problem was edited and its tests are read again.
+type Fingerprint = Vec<(String, u64, Option<SystemTime>)>;
+
+struct CachedTests {
+    fingerprint: Fingerprint,
+    samples: HashMap<String, bool>,
+    data: Arc<TestData>,
+    last_used: u64,
+}
+
+/// Keeps the test data of the `capacity` most recently judged problems in
+/// memory so each submission doesn't read them from disk again.
+#[derive(Clone)]
+pub struct TestDataCache {
+    problems: Arc<Mutex<HashMap<(String, String), CachedTests>>>,
+    uses: Arc<AtomicU64>,
+    capacity: usize,
+}
+
+impl TestDataCache {
+    pub fn new(capacity: usize) -> Self {
+        TestDataCache {
+            problems: Arc::new(Mutex::new(HashMap::new())),
+            uses: Arc::new(AtomicU64::new(0)),
+            capacity,
+        }
+    }
+
+    /// `samples` marks test cases as sample or hidden regardless of their name.
+    pub async fn load(&self, inputs_dir: &str, outputs_dir: &str, samples: &HashMap<String, bool>) -> Result<Arc<TestData>, String> {
+        let key = (inputs_dir.to_string(), outputs_dir.to_string());
+
+        let mut current = fingerprint(inputs_dir).await?;
+        current.extend(fingerprint(outputs_dir).await?);
+
+        let tick = self.uses.fetch_add(1, AtomicOrdering::Relaxed);
+
+        if let Some(cached) = self.problems.lock().unwrap().get_mut(&key)
+            && cached.fingerprint == current
+            && cached.samples == *samples
+        {
+            cached.last_used = tick;
+            return Ok(cached.data.clone());
+        }
+
+        let data = Arc::new(read_test_data(inputs_dir, outputs_dir, samples).await?);
+
+        if self.capacity > 0 {
+            let mut problems = self.problems.lock().unwrap();
+
+            if !problems.contains_key(&key)
+                && problems.len() >= self.capacity
+                && let Some(oldest) = problems
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.last_used)
+                    .map(|(key, _)| key.clone())
+            {
+                problems.remove(&oldest);
+            }
+
+            problems.insert(key, CachedTests { fingerprint: current, samples: samples.clone(), data: data.clone(), last_used: tick });
+        }
+
+        Ok(data)
+    }
+}
+
+async fn fingerprint(dir: &str) -> Result<Fingerprint, String> {
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(dir)
+        .await
+        .map_err(|_| format!("Error reading {}", dir))?;
+
+    while let Some(entry) = entries.next_entry().await.map_err(|_| format!("Error reading {}", dir))? {
+        let metadata = entry.metadata().await.map_err(|_| format!("Error reading {}", dir))?;
+        if metadata.is_file() {
+            files.push((entry.path().to_string_lossy().into_owned(), metadata.len(), metadata.modified().ok()));
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+async fn read_test_data(inputs_dir: &str, outputs_dir: &str, samples: &HashMap<String, bool>) -> Result<TestData, String> {
+    let inputs = read_dir_files(inputs_dir).await?;
+    let mut outputs = read_dir_files(outputs_dir).await?;
+
+    let mut cases = Vec::new();
+    for (id, input) in inputs {
+        let output = outputs
+            .remove(&id)
+            .ok_or_else(|| format!("File {} doesn't have expected output", id))?;
+
+        cases.push(TestCase {
+            sample: samples.get(&id).copied().unwrap_or_else(|| is_sample(&id)),
+            id,
+            input,
+            output,
+        });
+    }
+
+    cases.sort_by(|a, b| b.sample.cmp(&a.sample).then_with(|| natural_cmp(&a.id, &b.id)));
+
+    Ok(TestData { cases })
+}
+
+async fn read_dir_files(dir: &str) -> Result<HashMap<String, String>, String> {
+    let mut files = HashMap::new();
+    let mut entries = fs::read_dir(dir)
+        .await
+        .map_err(|_| format!("Error reading {}", dir))?;
+
+    while let Some(entry) = entries.next_entry().await.map_err(|_| "Error reading the file")? {
+        let path = entry.path();
+        if path.is_file()
+            && let Some(file_name) = path.file_stem().and_then(|f| f.to_str())
+        {
+            let contenido = fs::read_to_string(&path)
+                .await
+                .map_err(|_| format!("Error reading the file {}", file_name))?;
+            files.insert(file_name.to_string(), contenido);
+        }
+    }
+
+    Ok(files)
+}
+
+// Sample tests are the ones the statement shows, named `sample1`, `sample2`…
+// unless the problem setter marked them otherwise.
+fn is_sample(id: &str) -> bool {
+    id.to_ascii_lowercase().starts_with("sample")
+}
+
+/// Compares names chunk by chunk, with runs of digits compared as numbers.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chunks = chunks(a).into_iter();
+    let mut b_chunks = chunks(b).into_iter();
+
+    loop {
+        let ordering = match (a_chunks.next(), b_chunks.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u128>(), y.parse::<u128>()) {
+                (Ok(x_number), Ok(y_number)) => x_number.cmp(&y_number),
+                _ => x.cmp(y),
+            },
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn chunks(name: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    for (index, c) in name.char_indices().skip(1) {
+        let previous = name[..index].chars().next_back().unwrap_or(c);
+        if previous.is_ascii_digit() != c.is_ascii_digit() {
+            chunks.push(&name[start..index]);
+            start = index;
+        }
+    }
+
+    if start < name.len() {
+        chunks.push(&name[start..]);
+    }
+
+    chunks
+}
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use sqlx::{PgPool, Row};
+use tokio::fs::read_to_string;
+use crate::judge::backend::JudgeBackend;
+use crate::judge::checker::{CheckerConfig, TESTLIB_LANGUAGE};
+use crate::judge::client::is_unavailable;
+use crate::judge::grader::{load_grader, with_grader};
+use crate::judge::languages;
+use crate::judge::pipeline::{compile, ProblemSpec, TestConcurrency};
+use crate::judge::queue::{self, JudgeQueue, Release};
+use crate::judge::status::StatusBroadcaster;
+use crate::judge::testdata::TestDataCache;
+use crate::models::compile_models::{CompileStruct, SourceFile};
+use crate::models::job_models::JudgeJob;
+use crate::models::result_models::JudgeOutcome;
+
+#[derive(Clone)]
+pub struct WorkerConfig {
+    pub workers: usize,
+    pub max_attempts: i32,
+    pub poll_interval: Duration,
+    pub stale_secs: i64,
+    pub heartbeat_interval: Duration,
+    pub sweep_interval: Duration,
+    pub run_all_cases: bool,
+    pub unavailable_retry_secs: i64,
+    pub cached_problems: usize,
+}
+
+impl WorkerConfig {
+    pub fn from_env() -> Self {
+        WorkerConfig {
+            workers: env_or("JUDGE_WORKERS", 4),
+            max_attempts: env_or("JUDGE_MAX_ATTEMPTS", 3),
+            poll_interval: Duration::from_millis(env_or("JUDGE_POLL_INTERVAL_MS", 2000)),
+            stale_secs: env_or("JUDGE_STALE_JOB_SECS", 60),
+            heartbeat_interval: Duration::from_secs(env_or("JUDGE_HEARTBEAT_SECS", 10)),
+            sweep_interval: Duration::from_secs(env_or("JUDGE_STALE_SWEEP_SECS", 30)),
+            run_all_cases: env_or("JUDGE_RUN_ALL_CASES", false),
+            unavailable_retry_secs: env_or("JUDGE_UNAVAILABLE_RETRY_SECS", 60),
+            cached_problems: env_or("JUDGE_CACHED_PROBLEMS", 32),
+        }
+    }
+}
+
+pub(crate) fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+// What every worker shares.
+struct WorkerContext {
+    pool: PgPool,
+    judge_queue: JudgeQueue,
+    status: StatusBroadcaster,
+    backend: Arc<dyn JudgeBackend>,
+    concurrency: TestConcurrency,
+    tests: TestDataCache,
+    config: WorkerConfig,
+}
+
+pub fn spawn_workers(pool: PgPool, judge_queue: JudgeQueue, status: StatusBroadcaster, backend: Arc<dyn JudgeBackend>, concurrency: TestConcurrency, config: WorkerConfig) {
+    let context = Arc::new(WorkerContext {
+        pool,
+        judge_queue,
+        status,
+        backend,
+        concurrency,
+        tests: TestDataCache::new(config.cached_problems),
+        config,
+    });
+
+    for worker_id in 0..context.config.workers {
+        tokio::spawn(supervise_worker(worker_id, context.clone()));
+    }
+
+    tokio::spawn(sweep_stale_jobs(context));
+}
+
+// A job that panics takes its worker down with it; a new one takes its place
+// and the job's lease runs out, so the sweep retries or fails it.
+async fn supervise_worker(worker_id: usize, context: Arc<WorkerContext>) {
+    loop {
+        let worker = tokio::spawn(run_worker(worker_id, context.clone()));
+
+        match worker.await {
+            Err(e) if e.is_panic() => eprintln!("Worker {} panicked, starting it again", worker_id),
+            _ => return,
+        }
+    }
+}
+
+async fn sweep_stale_jobs(context: Arc<WorkerContext>) {
+    let config = &context.config;
+    let mut interval = tokio::time::interval(config.sweep_interval);
+
+    loop {
+        interval.tick().await;
+
+        match queue::sweep_stale_jobs(&context.pool, config.stale_secs, config.max_attempts).await {
+            Ok(swept) => {
+                for (job, release) in swept {
+                    eprintln!("Submission {}: its worker stopped responding on attempt {}", job.submission_id, job.attempts);
+                    publish_release(&context.status, &job, release);
+                }
+            }
+            Err(e) => eprintln!("Cannot sweep stale jobs: {}", e),
+        }
+    }
+}
+
+async fn run_worker(worker_id: usize, context: Arc<WorkerContext>) {
+    let config = &context.config;
+    println!("Judge worker {} started with the {} backend", worker_id, context.backend.name());
+
+    loop {
+        // Leave the jobs queued while the judge is down instead of failing them.
+        if !context.backend.is_available() {
+            tokio::time::sleep(config.poll_interval).await;
+            continue;
+        }
+
+        match queue::claim_job(&context.pool).await {
+            Ok(Some(job)) => process_job(&context, &job).await,
+            Ok(None) => {
+                tokio::select! {
+                    _ = context.judge_queue.wait_for_job() => {}
+                    _ = tokio::time::sleep(config.poll_interval) => {}
+                }
+            }
+            Err(e) => {
+                eprintln!("Worker {}: cannot claim job: {}", worker_id, e);
+                tokio::time::sleep(config.poll_interval).await;
+            }
+        }
+    }
+}
+
+async fn process_job(context: &WorkerContext, job: &JudgeJob) {
+    let (pool, status, config) = (&context.pool, &context.status, &context.config);
+    let result = with_heartbeat(pool, job, config.heartbeat_interval, judge_job(context, job)).await;
+
+    let saved = match result {
+        Ok(outcome) => {
+            println!("Submission {} judged: {}", job.submission_id, outcome.veredict);
+            queue::complete_job(pool, job, &outcome).await.inspect(|&held| {
+                if held {
+                    status.finished(job.submission_id, job.user_id, &outcome.veredict, outcome.score);
+                }
+            })
+        }
+        Err(e) if is_unavailable(&e) => {
+            eprintln!("Submission {} will be judged again in {}s: {}", job.submission_id, config.unavailable_retry_secs, e);
+            // The stored verdict is IE until then, but the job is back in the queue.
+            queue::defer_job(pool, job, &e, config.unavailable_retry_secs).await.inspect(|&held| {
+                if held {
+                    status.queued(job.submission_id, job.user_id);
+                }
+            })
+        }
+        Err(e) => {
+            eprintln!("Submission {} failed on attempt {}: {}", job.submission_id, job.attempts, e);
+            queue::fail_job(pool, job, &e, config.max_attempts).await.map(|release| {
+                publish_release(status, job, release);
+                release != Release::LeaseLost
+            })
+        }
+    };
+
+    match saved {
+        Ok(true) => {}
+        Ok(false) => eprintln!("Job {} was swept as stale, its result is discarded", job.job_id),
+        Err(e) => eprintln!("Cannot save the result of job {}: {}", job.job_id, e),
+    }
+}
+
+fn publish_release(status: &StatusBroadcaster, job: &JudgeJob, release: Release) {
+    match release {
+        Release::Requeued => status.queued(job.submission_id, job.user_id),
+        Release::Failed => status.finished(job.submission_id, job.user_id, "IE", 0),
+        Release::LeaseLost => {}
+    }
+}
+
+// Renews the job's lease every `interval` while `judging` runs.
+async fn with_heartbeat<F: Future>(pool: &PgPool, job: &JudgeJob, interval: Duration, judging: F) -> F::Output {
+    let mut heartbeat = tokio::time::interval(interval);
+    heartbeat.tick().await;
+    tokio::pin!(judging);
+
+    loop {
+        tokio::select! {
+            output = &mut judging => return output,
+            _ = heartbeat.tick() => {
+                if let Err(e) = queue::heartbeat(pool, job).await {
+                    eprintln!("Cannot renew the lease of job {}: {}", job.job_id, e);
+                }
+            }
+        }
+    }
+}
+
+async fn judge_job(context: &WorkerContext, job: &JudgeJob) -> Result<JudgeOutcome, String> {
+    let (pool, backend) = (&context.pool, context.backend.as_ref());
+
+    let query = "
+        SELECT
+            S.SUBMISSION_URL,
+            S.SUBMISSION_FILE_NAME,
+            S.PROBLEM_ID,
+            P.PROBLEM_TEST_CASES_URL,
+            P.PROBLEM_OUTPUTS_URL,
+            P.PROBLEM_MEMORY_MB_LIMIT,
+            P.PROBLEM_TIME_MS_LIMIT,
+            P.PROBLEM_CHECKER_MODE,
+            P.PROBLEM_CHECKER_EPSILON,
+            P.PROBLEM_CHECKER_URL,
+            P.PROBLEM_INTERACTOR_URL,
+            P.PROBLEM_SUBTASKS_URL,
+            P.PROBLEM_GRADER_URL
+        FROM SUBMISSIONS S
+        JOIN PROBLEMS P ON P.PROBLEM_ID = S.PROBLEM_ID
+        WHERE S.SUBMISSION_ID = $1
+    ";
+
+    let row = sqlx::query(query)
+        .bind(job.submission_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+
+    let language = languages::get_language(pool, &job.lang)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?
+        .ok_or_else(|| format!("Language {} is not registered", job.lang))?;
+
+    let testlib_version = languages::get_language(pool, TESTLIB_LANGUAGE)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?
+        .map(|testlib| testlib.version);
+
+    let mut files = load_submission_files(pool, job.submission_id, row.get("submission_url"), row.get("submission_file_name")).await?;
+
+    let grader_url: Option<String> = row.get("problem_grader_url");
+    if let Some(grader_url) = grader_url {
+        let grader = load_grader(&grader_url, &job.lang).await?;
+        files = with_grader(grader, files, &language.extension);
+    }
+
+    let source = CompileStruct {
+        lang: job.lang.clone(),
+        version: job.version.clone(),
+        files,
+        memory_limit: language.memory_limit(row.get("problem_memory_mb_limit")),
+        time_limit: language.time_limit(row.get("problem_time_ms_limit")),
+    };
+
+    let test_cases_url: String = row.get("problem_test_cases_url");
+    let outputs_url: String = row.get("problem_outputs_url");
+    let samples = load_sample_marks(pool, row.get("problem_id")).await?;
+    let tests = context.tests.load(&test_cases_url, &outputs_url, &samples).await?;
+
+    let problem = ProblemSpec {
+        tests,
+        checker: CheckerConfig {
+            mode: row.get("problem_checker_mode"),
+            epsilon: row.get("problem_checker_epsilon"),
+            checker_url: row.get("problem_checker_url"),
+            testlib_version,
+        },
+        interactor_url: row.get("problem_interactor_url"),
+        subtasks_url: row.get("problem_subtasks_url"),
+    };
+
+    let progress = context.status.for_submission(job.submission_id, job.user_id);
+
+    compile(backend, source, &problem, context.config.run_all_cases, &context.concurrency, &progress).await
+}
+
+async fn load_sample_marks(pool: &PgPool, problem_id: i32) -> Result<HashMap<String, bool>, String> {
+    let rows = sqlx::query("SELECT TEST_CASE_ID, TEST_CASE_SAMPLE FROM PROBLEM_TEST_CASES WHERE PROBLEM_ID = $1")
+        .bind(problem_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+
+    Ok(rows.iter().map(|row| (row.get("test_case_id"), row.get("test_case_sample"))).collect())
+}
+
+// The submitted files in the order they were uploaded, the first one being SUBMISSION_URL.
+pub async fn load_submission_files(pool: &PgPool, submission_id: i32, submission_url: String, file_name: Option<String>) -> Result<Vec<SourceFile>, String> {
+    let mut locations = vec![(file_name, submission_url)];
+
+    let rows = sqlx::query("SELECT FILE_NAME, FILE_URL FROM SUBMISSION_FILES WHERE SUBMISSION_ID = $1 ORDER BY FILE_ORDER ASC")
+        .bind(submission_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+    locations.extend(rows.iter().map(|row| (Some(row.get("file_name")), row.get("file_url"))));
+
+    let mut files = Vec::new();
+    for (name, url) in locations {
+        let content = read_to_string(&url)
+            .await
+            .map_err(|e| format!("Cannot read the source file {}: {}", url, e))?;
+        files.push(SourceFile { name, content });
+    }
+
+    Ok(files)
+}
@@ -1,18 +1,49 @@
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use crate::routes::routes::create_router;
+use crate::judge::backend::backend_from_env;
+use crate::judge::languages::discover_languages;
+use crate::judge::pipeline::TestConcurrency;
+use crate::judge::queue::JudgeQueue;
+use crate::judge::runner::Runner;
+use crate::judge::status::StatusBroadcaster;
+use crate::judge::worker::{spawn_workers, WorkerConfig};
+use crate::utils::rate_limit::SubmissionLimits;
 
 mod handlers;
 mod database;
 mod routes;
 mod models;
+mod judge;
+mod plagiarism;
+mod utils;
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
     let db_pool = database::init_db().await.expect("Failed to connect to database");
     
-    let app = create_router().layer(axum::extract::Extension(db_pool));
+    let backend = backend_from_env();
+
+    if std::env::var("JUDGE_DISCOVER_LANGUAGES").is_ok_and(|v| v == "true")
+        && let Err(e) = discover_languages(&db_pool, backend.as_ref()).await
+    {
+        eprintln!("Cannot discover the judge languages: {}", e);
+    }
+
+    let run_concurrency = std::env::var("JUDGE_RUN_CONCURRENCY").ok().and_then(|v| v.parse().ok()).unwrap_or(2);
+    let runner = Runner::new(backend.clone(), run_concurrency);
+
+    let judge_queue = JudgeQueue::new();
+    let status = StatusBroadcaster::new(db_pool.clone());
+    spawn_workers(db_pool.clone(), judge_queue.clone(), status.clone(), backend, TestConcurrency::from_env(), WorkerConfig::from_env());
+
+    let app = create_router()
+        .layer(axum::extract::Extension(db_pool))
+        .layer(axum::extract::Extension(judge_queue))
+        .layer(axum::extract::Extension(status))
+        .layer(axum::extract::Extension(runner))
+        .layer(axum::extract::Extension(SubmissionLimits::from_env()));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 8000));
 
@@ -1,16 +1,27 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct CompileStruct {
     pub lang:String,
     pub version: String,
-    pub source_code: String,
+    /// Compiled together; the first one is the entry point.
+    pub files: Vec<SourceFile>,
     pub memory_limit: i32,
     pub time_limit: i32,
 }
 
-#[derive(Deserialize, Serialize)]
+impl CompileStruct {
+    /// CPU time decides TLE; the wall clock limit only stops programs that
+    /// sleep or wait on input.
+    pub fn wall_time_limit(&self) -> u64 {
+        self.time_limit.max(1) as u64 * 2 + 500
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct SourceFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub content: String,
 }
 
@@ -20,22 +31,40 @@ pub struct CompileRequest {
     pub version:String,
     pub files: Vec<SourceFile>,
     pub stdin: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compile_timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_cpu_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_memory_limit: Option<i64>,
 }
 
-#[derive(Debug,Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ResultObject {
     pub stdout:String,
+    #[serde(default)]
+    pub stderr:String,
     pub code:Option<i32>,
     pub signal:Option<String>,
     pub wall_time:Option<i32>,
+    #[serde(default)]
+    pub cpu_time:Option<i32>,
     pub memory:Option<i32>,
+    /// Piston's run status: `TO` time limit, `OL`/`EL` stdout/stderr limit,
+    /// `SG` killed by a signal, `RE` runtime error, `XX` judge failure.
+    #[serde(default)]
+    pub status:Option<String>,
 }
 
 #[derive(Debug,Serialize,Deserialize)]
 pub struct CompileResponse {
     pub language: String,
     pub version: String,
-    pub run: ResultObject,
+    // Missing when the compile step failed.
+    pub run: Option<ResultObject>,
     pub compile: Option<ResultObject>,
 }
-
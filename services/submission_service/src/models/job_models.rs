@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+pub struct JudgeJob {
+    pub job_id: i32,
+    pub submission_id: i32,
+    pub user_id: i32,
+    pub lang: String,
+    pub version: String,
+    pub attempts: i32,
+}
+
+/// Which submissions a rejudge applies to. Every filter that is set must match.
+#[derive(Deserialize)]
+pub struct RejudgeFilter {
+    #[serde(default)]
+    pub submission_id: Option<i32>,
+    #[serde(default)]
+    pub problem_id: Option<i32>,
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+}
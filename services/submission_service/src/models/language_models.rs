@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Language {
+    pub name: String,
+    pub display_name: String,
+    pub version: String,
+    pub extension: String,
+    pub time_multiplier: f64,
+    pub memory_overhead_mb: i32,
+    pub enabled: bool,
+}
+
+impl Language {
+    pub fn time_limit(&self, problem_time_ms: i32) -> i32 {
+        (problem_time_ms as f64 * self.time_multiplier).round() as i32
+    }
+
+    pub fn memory_limit(&self, problem_memory_mb: i32) -> i32 {
+        problem_memory_mb + self.memory_overhead_mb
+    }
+}
+
+/// A language and version the judge can run, as listed by the backend.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Runtime {
+    pub language: String,
+    pub version: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
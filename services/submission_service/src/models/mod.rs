@@ -1 +1,10 @@
-pub mod compile_models;
\ No newline at end of file
+pub mod auth_models;
+pub mod compile_models;
+pub mod job_models;
+pub mod language_models;
+pub mod plagiarism_models;
+pub mod result_models;
+pub mod run_models;
+pub mod status_models;
+pub mod submission_models;
+pub mod subtask_models;
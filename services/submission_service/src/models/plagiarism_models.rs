@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct PlagiarismRequest {
+    #[serde(default)]
+    pub problem_id: Option<i32>,
+    #[serde(default)]
+    pub contest_id: Option<i32>,
+    #[serde(default)]
+    pub threshold: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchRegion {
+    pub a_start_line: i32,
+    pub a_end_line: i32,
+    pub b_start_line: i32,
+    pub b_end_line: i32,
+}
+
+#[derive(Serialize)]
+pub struct PairSummary {
+    pub pair_id: i32,
+    pub problem_id: i32,
+    pub submission_a: i32,
+    pub user_a: i32,
+    pub submission_b: i32,
+    pub user_b: i32,
+    pub similarity: f64,
+}
+
+#[derive(Serialize)]
+pub struct PlagiarismReport {
+    pub report_id: i32,
+    pub problem_id: Option<i32>,
+    pub contest_id: Option<i32>,
+    pub threshold: f64,
+    pub submissions: i32,
+    pub requested_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub pairs: Vec<PairSummary>,
+}
+
+#[derive(Serialize)]
+pub struct PairDetail {
+    #[serde(flatten)]
+    pub pair: PairSummary,
+    pub source_a: String,
+    pub source_b: String,
+    pub regions: Vec<MatchRegion>,
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestCaseResult {
+    pub test_case: String,
+    pub veredict: String,
+    pub wall_time: Option<i32>,
+    pub memory: Option<i32>,
+    pub signal: Option<String>,
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubtaskResult {
+    pub subtask_id: i32,
+    pub veredict: String,
+    pub score: i32,
+    pub earned_score: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerdictChange {
+    pub job_id: Option<i32>,
+    pub requested_by: Option<i32>,
+    pub previous_veredict: String,
+    pub previous_score: i32,
+    pub veredict: String,
+    pub score: i32,
+    pub changed_at: DateTime<Utc>,
+}
+
+pub struct JudgeOutcome {
+    pub veredict: String,
+    pub score: i32,
+    pub results: Vec<TestCaseResult>,
+    pub subtasks: Vec<SubtaskResult>,
+    pub compile_output: Option<String>,
+}
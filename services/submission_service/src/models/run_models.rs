@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct RunRequest {
+    pub lang: String,
+    pub source: String,
+    #[serde(default)]
+    pub stdin: String,
+    #[serde(default)]
+    pub problem_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunResult {
+    pub status: String,
+    pub compile_output: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub signal: Option<String>,
+    pub wall_time: Option<i32>,
+    pub cpu_time: Option<i32>,
+    pub memory: Option<i32>,
+    pub time_limit: i32,
+    pub memory_limit: i32,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusEvent {
+    pub submission_id: i32,
+    pub user_id: i32,
+    pub state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub veredict: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<i32>,
+}
+
+impl StatusEvent {
+    pub fn new(submission_id: i32, user_id: i32, state: &str) -> Self {
+        StatusEvent {
+            submission_id,
+            user_id,
+            state: state.to_string(),
+            test: None,
+            total: None,
+            veredict: None,
+            score: None,
+        }
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.state == "FINISHED"
+    }
+}
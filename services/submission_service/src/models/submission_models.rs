@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmissionSort {
+    #[default]
+    Oldest,
+    Newest,
+    /// Lowest maximum time first.
+    Fastest,
+    /// Lowest maximum memory first.
+    Lightest,
+}
+
+/// Query string of `GET /`. Every filter that is set must match.
+#[derive(Deserialize)]
+pub struct SubmissionQuery {
+    #[serde(default)]
+    pub user_id: Option<i32>,
+    #[serde(default)]
+    pub problem_id: Option<i32>,
+    #[serde(default)]
+    pub contest_id: Option<i32>,
+    #[serde(default)]
+    pub veredict: Option<String>,
+    #[serde(default)]
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub sort: SubmissionSort,
+    /// `next_cursor` of the previous page.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+    /// Leaves out `submission_content` so no source is read from disk.
+    #[serde(default)]
+    pub summary: bool,
+}
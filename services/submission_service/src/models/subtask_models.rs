@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Subtask {
+    pub id: i32,
+    pub score: i32,
+    pub tests: Vec<String>,
+    #[serde(default)]
+    pub depends_on: Vec<i32>,
+}
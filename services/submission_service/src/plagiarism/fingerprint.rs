@@ -0,0 +1,61 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use crate::plagiarism::tokenizer::Token;
+
+// Matches shorter than KGRAM tokens are ignored; any match of at least
+// KGRAM + WINDOW - 1 tokens is guaranteed to be found.
+const KGRAM: usize = 5;
+const WINDOW: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct Fingerprint {
+    pub hash: u64,
+    pub start_line: i32,
+    pub end_line: i32,
+}
+
+/// Winnowing (Schleimer et al.): hash every k-gram of tokens and keep the
+/// minimum hash of each window, so copied fragments share fingerprints
+/// regardless of where they sit in the file.
+pub fn fingerprints(tokens: &[Token]) -> Vec<Fingerprint> {
+    if tokens.len() < KGRAM {
+        return Vec::new();
+    }
+
+    let hashes: Vec<u64> = tokens
+        .windows(KGRAM)
+        .map(|kgram| {
+            let mut hasher = DefaultHasher::new();
+            for token in kgram {
+                token.text.hash(&mut hasher);
+            }
+            hasher.finish()
+        })
+        .collect();
+
+    let mut selected: Vec<Fingerprint> = Vec::new();
+    let mut last_position = None;
+
+    for start in 0..hashes.len().saturating_sub(WINDOW - 1).max(1) {
+        let end = (start + WINDOW).min(hashes.len());
+
+        // Rightmost minimum, so a run of equal hashes is only recorded once.
+        let mut position = start;
+        for candidate in start..end {
+            if hashes[candidate] <= hashes[position] {
+                position = candidate;
+            }
+        }
+
+        if last_position != Some(position) {
+            selected.push(Fingerprint {
+                hash: hashes[position],
+                start_line: tokens[position].line,
+                end_line: tokens[position + KGRAM - 1].line,
+            });
+            last_position = Some(position);
+        }
+    }
+
+    selected
+}
@@ -0,0 +1,106 @@
+pub mod fingerprint;
+pub mod tokenizer;
+
+use std::collections::{HashMap, HashSet};
+use crate::models::plagiarism_models::MatchRegion;
+use crate::plagiarism::fingerprint::{fingerprints, Fingerprint};
+use crate::plagiarism::tokenizer::tokenize;
+
+pub struct Document {
+    pub submission_id: i32,
+    pub user_id: i32,
+    fingerprints: Vec<Fingerprint>,
+}
+
+impl Document {
+    pub fn new(submission_id: i32, user_id: i32, source: &str, hash_comments: bool) -> Self {
+        Document {
+            submission_id,
+            user_id,
+            fingerprints: fingerprints(&tokenize(source, hash_comments)),
+        }
+    }
+}
+
+pub struct SuspiciousPair {
+    pub submission_a: i32,
+    pub submission_b: i32,
+    pub similarity: f64,
+    pub regions: Vec<MatchRegion>,
+}
+
+/// Compares every pair of documents from different users and keeps the ones
+/// at or above `threshold`, most similar first.
+pub fn find_suspicious_pairs(documents: &[Document], threshold: f64) -> Vec<SuspiciousPair> {
+    let mut pairs = Vec::new();
+
+    for (i, a) in documents.iter().enumerate() {
+        for b in &documents[i + 1..] {
+            if a.user_id == b.user_id {
+                continue;
+            }
+
+            let similarity = similarity(a, b);
+            if similarity >= threshold && similarity > 0.0 {
+                pairs.push(SuspiciousPair {
+                    submission_a: a.submission_id,
+                    submission_b: b.submission_id,
+                    similarity,
+                    regions: matching_regions(a, b),
+                });
+            }
+        }
+    }
+
+    pairs.sort_by(|x, y| y.similarity.total_cmp(&x.similarity));
+    pairs
+}
+
+// Dice coefficient over the distinct fingerprints of both documents.
+fn similarity(a: &Document, b: &Document) -> f64 {
+    let hashes_a: HashSet<u64> = a.fingerprints.iter().map(|fp| fp.hash).collect();
+    let hashes_b: HashSet<u64> = b.fingerprints.iter().map(|fp| fp.hash).collect();
+
+    if hashes_a.is_empty() || hashes_b.is_empty() {
+        return 0.0;
+    }
+
+    let shared = hashes_a.intersection(&hashes_b).count();
+    2.0 * shared as f64 / (hashes_a.len() + hashes_b.len()) as f64
+}
+
+/// Line ranges of `a` and `b` covered by shared fingerprints, with
+/// neighbouring matches merged into one region.
+fn matching_regions(a: &Document, b: &Document) -> Vec<MatchRegion> {
+    let mut in_b: HashMap<u64, &Fingerprint> = HashMap::new();
+    for fp in &b.fingerprints {
+        in_b.entry(fp.hash).or_insert(fp);
+    }
+
+    let mut regions: Vec<MatchRegion> = Vec::new();
+
+    for fp in &a.fingerprints {
+        let Some(other) = in_b.get(&fp.hash) else {
+            continue;
+        };
+
+        if let Some(last) = regions.last_mut()
+            && fp.start_line <= last.a_end_line + 1
+            && other.start_line >= last.b_start_line
+            && other.start_line <= last.b_end_line + 1
+        {
+            last.a_end_line = last.a_end_line.max(fp.end_line);
+            last.b_end_line = last.b_end_line.max(other.end_line);
+            continue;
+        }
+
+        regions.push(MatchRegion {
+            a_start_line: fp.start_line,
+            a_end_line: fp.end_line,
+            b_start_line: other.start_line,
+            b_end_line: other.end_line,
+        });
+    }
+
+    regions
+}
@@ -0,0 +1,83 @@
+/// A normalized token and the source line it starts on. Identifiers, numbers
+/// and literals collapse to one token each, so renaming variables or changing
+/// constants does not hide copied code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub text: String,
+    pub line: i32,
+}
+
+const KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum",
+    "extern", "float", "for", "goto", "if", "int", "long", "register", "return", "short", "signed",
+    "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void", "volatile", "while",
+    "bool", "class", "delete", "namespace", "new", "operator", "private", "protected", "public",
+    "template", "this", "throw", "try", "catch", "using", "virtual", "true", "false", "nullptr",
+    "abstract", "boolean", "byte", "extends", "final", "finally", "implements", "import", "instanceof",
+    "interface", "package", "super", "throws", "null", "and", "as", "assert", "def", "del", "elif",
+    "except", "from", "global", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "with",
+    "yield", "None", "True", "False",
+];
+
+pub fn tokenize(source: &str, hash_comments: bool) -> Vec<Token> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if (hash_comments && c == '#') || (c == '/' && chars.get(i + 1) == Some(&'/')) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' || c == '\'' {
+            let start_line = line;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                if chars.get(i) == Some(&'\n') {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token { text: "str".to_string(), line: start_line });
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.' || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token { text: "num".to_string(), line });
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let text = if KEYWORDS.contains(&word.as_str()) { word } else { "id".to_string() };
+            tokens.push(Token { text, line });
+        } else {
+            tokens.push(Token { text: c.to_string(), line });
+            i += 1;
+        }
+    }
+
+    tokens
+}
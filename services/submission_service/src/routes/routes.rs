@@ -3,13 +3,31 @@ use axum::routing::{post,get};
 use crate::handlers::upload_code_handler::upload;
 use crate::handlers::get_submissions_handler::{get_submissions, get_submission_by_id, get_submissions_by_user_id};
 use crate::handlers::get_attemps_handler::get_attemps;
+use crate::handlers::languages_handler::get_languages;
+use crate::handlers::plagiarism_handler::{create_plagiarism_report, get_plagiarism_pair, get_plagiarism_report};
+use crate::handlers::run_handler::run_code;
+use crate::handlers::source_handler::{diff_submissions, get_submission_source};
+use crate::handlers::status_handler::{get_submission_events, get_user_events};
+use crate::handlers::rejudge_handler::{get_verdict_history, rejudge_submission, rejudge_submissions};
 
 pub fn create_router() -> Router {
     Router::new()
         .route("/", post(upload))
         .route("/", get(get_submissions))
+        .route("/languages", get(get_languages))
+        .route("/run", post(run_code))
+        .route("/diff", get(diff_submissions))
         .route("/{id}", get(get_submission_by_id))
         .route("/attemps", post(get_attemps))
         .route("/user/{id}", get(get_submissions_by_user_id))
+        .route("/rejudge", post(rejudge_submissions))
+        .route("/{id}/rejudge", post(rejudge_submission))
+        .route("/{id}/history", get(get_verdict_history))
+        .route("/{id}/source", get(get_submission_source))
+        .route("/{id}/events", get(get_submission_events))
+        .route("/user/{id}/events", get(get_user_events))
+        .route("/plagiarism", post(create_plagiarism_report))
+        .route("/plagiarism/{id}", get(get_plagiarism_report))
+        .route("/plagiarism/pairs/{id}", get(get_plagiarism_pair))
 }
 
@@ -0,0 +1,98 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use serde_json::{json, Value};
+use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+use std::env;
+use crate::models::auth_models::Claims;
+
+pub struct AuthenticatedUser(pub Claims);
+
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let token = bearer_token(parts).ok_or(StatusCode::UNAUTHORIZED)?;
+        decode_token(token).map(AuthenticatedUser)
+    }
+}
+
+/// Same as `AuthenticatedUser`, but also takes the token from `?token=`,
+/// since EventSource cannot set headers. Only for the event streams: a token
+/// in the URL ends up in access logs.
+pub struct EventStreamUser(pub Claims);
+
+impl<S> FromRequestParts<S> for EventStreamUser
+where
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let token = match parts.headers.get("authorization") {
+            Some(_) => bearer_token(parts),
+            None => parts
+                .uri
+                .query()
+                .and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix("token="))),
+        };
+
+        let token = token.ok_or(StatusCode::UNAUTHORIZED)?;
+        decode_token(token).map(EventStreamUser)
+    }
+}
+
+fn bearer_token(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+}
+
+fn decode_token(token: &str) -> Result<Claims, StatusCode> {
+    let secret = env::var("JWT_SECRET").expect("JWT_SECRET not set");
+
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(secret.as_ref()),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map(|decoded| decoded.claims)
+    .map_err(|_| StatusCode::UNAUTHORIZED)
+}
+
+/// Id of the authenticated professor, or the error to answer anyone else with.
+pub fn professor_id(claims: &Claims) -> Result<i32, (StatusCode, Json<Value>)> {
+    if !claims.is_professor() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Only professors can do this" }))
+        ));
+    }
+
+    claims.user_id().ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Invalid token subject" }))
+        )
+    })
+}
+
+/// The only user whose submissions the caller may see, or `None` for professors.
+pub fn visible_owner(claims: &Claims) -> Result<Option<i32>, StatusCode> {
+    if claims.is_professor() {
+        return Ok(None);
+    }
+
+    claims.user_id().map(Some).ok_or(StatusCode::UNAUTHORIZED)
+}
+
+pub fn can_view(claims: &Claims, owner_id: i32) -> bool {
+    claims.is_professor() || claims.user_id() == Some(owner_id)
+}
@@ -0,0 +1,118 @@
+use sqlx::{PgPool, Row};
+use crate::judge::worker::env_or;
+
+/// A token bucket: up to `burst` submissions at once, refilled at
+/// `per_minute`. A zero burst or rate turns the limit off.
+#[derive(Clone, Copy)]
+pub struct BucketLimit {
+    pub burst: f64,
+    pub per_minute: f64,
+}
+
+impl BucketLimit {
+    fn enabled(&self) -> bool {
+        self.burst > 0.0 && self.per_minute > 0.0
+    }
+}
+
+#[derive(Clone)]
+pub struct SubmissionLimits {
+    pub per_user: BucketLimit,
+    pub per_problem: BucketLimit,
+}
+
+impl SubmissionLimits {
+    pub fn from_env() -> Self {
+        SubmissionLimits {
+            per_user: BucketLimit {
+                burst: env_or("SUBMISSION_USER_BURST", 10.0),
+                per_minute: env_or("SUBMISSION_USER_PER_MINUTE", 6.0),
+            },
+            per_problem: BucketLimit {
+                burst: env_or("SUBMISSION_PROBLEM_BURST", 5.0),
+                per_minute: env_or("SUBMISSION_PROBLEM_PER_MINUTE", 2.0),
+            },
+        }
+    }
+}
+
+pub enum RateLimit {
+    Allowed,
+    Limited { retry_after_secs: u64 },
+}
+
+/// Takes a token from the user's bucket and from their bucket for the problem,
+/// or from neither if one of them is empty. The buckets live in the database
+/// so every replica of the service shares them.
+pub async fn take_submission_token(
+    pool: &PgPool,
+    limits: &SubmissionLimits,
+    user_id: i32,
+    problem_id: i32,
+) -> Result<RateLimit, sqlx::Error> {
+    let mut buckets = Vec::new();
+    if limits.per_user.enabled() {
+        buckets.push((format!("user:{}", user_id), limits.per_user));
+    }
+    if limits.per_problem.enabled() {
+        buckets.push((format!("user:{}:problem:{}", user_id, problem_id), limits.per_problem));
+    }
+
+    if buckets.is_empty() {
+        return Ok(RateLimit::Allowed);
+    }
+
+    // Always lock the buckets in the same order so two uploads can't deadlock.
+    buckets.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut tx = pool.begin().await?;
+    let mut remaining = Vec::new();
+    let mut retry_after: f64 = 0.0;
+
+    for (key, limit) in &buckets {
+        sqlx::query("INSERT INTO SUBMISSION_RATE_LIMITS (BUCKET_KEY, BUCKET_TOKENS) VALUES ($1, $2) ON CONFLICT (BUCKET_KEY) DO NOTHING")
+            .bind(key)
+            .bind(limit.burst)
+            .execute(&mut *tx)
+            .await?;
+
+        let row = sqlx::query("
+            SELECT
+                BUCKET_TOKENS,
+                GREATEST(EXTRACT(EPOCH FROM CLOCK_TIMESTAMP() - BUCKET_UPDATED_AT), 0)::FLOAT8 AS ELAPSED
+            FROM SUBMISSION_RATE_LIMITS
+            WHERE BUCKET_KEY = $1
+            FOR UPDATE
+        ")
+            .bind(key)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let per_second = limit.per_minute / 60.0;
+        let tokens: f64 = row.get("bucket_tokens");
+        let elapsed: f64 = row.get("elapsed");
+        let tokens = (tokens + elapsed * per_second).min(limit.burst);
+
+        if tokens < 1.0 {
+            retry_after = retry_after.max((1.0 - tokens) / per_second);
+        }
+        remaining.push(tokens - 1.0);
+    }
+
+    if retry_after > 0.0 {
+        tx.rollback().await?;
+        return Ok(RateLimit::Limited { retry_after_secs: retry_after.ceil() as u64 });
+    }
+
+    for ((key, _), tokens) in buckets.iter().zip(remaining) {
+        sqlx::query("UPDATE SUBMISSION_RATE_LIMITS SET BUCKET_TOKENS = $2, BUCKET_UPDATED_AT = CLOCK_TIMESTAMP() WHERE BUCKET_KEY = $1")
+            .bind(key)
+            .bind(tokens)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(RateLimit::Allowed)
+}